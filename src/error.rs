@@ -14,14 +14,82 @@ pub enum Error {
     Io(io::Error),
     /// Error originating from the cfb implementation.
     Cfb(io::Error),
-    // TODO: Add details to make the diagnostic more meaningful to clients.
     /// Error originating from the `CompressedContainer` decompressor.
-    Decompressor,
-    // TODO: Add details to make the diagnostic more meaningful to clients.
-    /// Generic parsing error.
-    Parser,
+    Decompressor {
+        /// The offset into the `CompressedContainer`, at which the error was detected.
+        offset: usize,
+        /// A description of the malformed structure.
+        reason: &'static str,
+    },
+    /// Error originating from the *dir* stream parser, or any of the other binary stream
+    /// parsers.
+    Parser {
+        /// The offset into the (decompressed) stream, at which the error was detected.
+        offset: usize,
+        /// The name of the record that failed to parse.
+        context: &'static str,
+    },
+    /// A *dir* stream record has an ID other than the one required by MS-OVBA.
+    UnexpectedRecord {
+        /// The record ID required at this point.
+        expected: u16,
+        /// The record ID found in the stream.
+        found: u16,
+        /// The offset of the record into the decompressed stream.
+        offset: usize,
+    },
+    /// Text cannot be represented in the project's code page.
+    Encoding {
+        /// The project's code page.
+        code_page: u16,
+    },
+    /// The data is a valid CFB, but doesn't contain a VBA project.
+    NotAVbaProject,
+    /// A module with the given name already exists.
+    DuplicateModule(string::String),
+    /// A module name isn't a valid VBA identifier.
+    InvalidModuleName(string::String),
+    /// An offset points past the end of a stream.
+    InvalidOffset {
+        /// The requested offset.
+        offset: usize,
+        /// The length of the stream.
+        len: usize,
+    },
+    /// The project's code page cannot be mapped to a supported encoding.
+    UnsupportedCodePage(u16),
     /// Requested module cannot be found.
     ModuleNotFound(string::String),
+    /// A stream was parsed successfully, but not all of its data was consumed.
+    TrailingData {
+        /// The number of bytes consumed by the parser.
+        consumed: usize,
+        /// The total number of bytes in the stream.
+        total: usize,
+    },
+    /// The number of modules declared in the *dir* stream's PROJECTMODULES Record
+    /// disagrees with the number of MODULE Records.
+    ModuleCountMismatch {
+        /// The number of modules declared.
+        declared: u16,
+        /// The number of MODULE Records found.
+        found: usize,
+    },
+    /// The requested stream (or storage) doesn't exist in the CFB.
+    MissingStream {
+        /// The stream's path.
+        path: string::String,
+    },
+    /// A module's text offset doesn't point to a `CompressedContainer`.
+    InvalidTextOffset {
+        /// The module's name.
+        module: string::String,
+        /// The module's text offset.
+        offset: usize,
+    },
+    /// The input isn't a CFB, but a file format known not to hold a VBA project in the
+    /// MS-OVBA format, such as an OpenDocument package.
+    UnsupportedFormat(&'static str),
 }
 
 impl From<io::Error> for Error {
@@ -38,9 +106,21 @@ impl error::Error for Error {
         match self {
             Error::Io(e) => Some(e),
             Error::Cfb(e) => Some(e),
-            Error::Decompressor => None,
-            Error::Parser => None,
+            Error::Decompressor { .. } => None,
+            Error::Parser { .. } => None,
+            Error::UnexpectedRecord { .. } => None,
+            Error::Encoding { .. } => None,
+            Error::NotAVbaProject => None,
+            Error::DuplicateModule(_) => None,
+            Error::InvalidModuleName(_) => None,
+            Error::InvalidOffset { .. } => None,
+            Error::UnsupportedCodePage(_) => None,
             Error::ModuleNotFound(_) => None,
+            Error::TrailingData { .. } => None,
+            Error::ModuleCountMismatch { .. } => None,
+            Error::MissingStream { .. } => None,
+            Error::InvalidTextOffset { .. } => None,
+            Error::UnsupportedFormat(_) => None,
         }
     }
 }
@@ -50,9 +130,49 @@ impl fmt::Display for Error {
         match self {
             Error::Io(e) => write!(f, "I/O error: {}", e),
             Error::Cfb(e) => write!(f, "CFB error: {}", e),
-            Error::Decompressor => write!(f, "Decompressor error"),
-            Error::Parser => write!(f, "Parse error"),
+            Error::Decompressor { offset, reason } => {
+                write!(f, "Decompressor error at offset {} ({})", offset, reason)
+            }
+            Error::Parser { offset, context } => {
+                write!(f, "Parse error at offset {} ({})", offset, context)
+            }
+            Error::UnexpectedRecord {
+                expected,
+                found,
+                offset,
+            } => write!(
+                f,
+                "Unexpected record at offset {}: expected ID 0x{:04X}, found 0x{:04X}",
+                offset, expected, found
+            ),
+            Error::Encoding { code_page } => {
+                write!(f, "Text cannot be encoded using code page {}", code_page)
+            }
+            Error::NotAVbaProject => write!(f, "Not a VBA project"),
+            Error::DuplicateModule(name) => write!(f, r#"Module "{}" already exists"#, name),
+            Error::InvalidModuleName(name) => write!(f, r#"Invalid module name "{}""#, name),
+            Error::InvalidOffset { offset, len } => {
+                write!(f, "Offset {} exceeds stream length {}", offset, len)
+            }
+            Error::UnsupportedCodePage(code_page) => {
+                write!(f, "Unsupported code page {}", code_page)
+            }
             Error::ModuleNotFound(name) => write!(f, r#"Module "{}" not found"#, name),
+            Error::TrailingData { consumed, total } => {
+                write!(f, "Trailing data: {} of {} bytes consumed", consumed, total)
+            }
+            Error::ModuleCountMismatch { declared, found } => write!(
+                f,
+                "Module count mismatch: {} declared, {} found",
+                declared, found
+            ),
+            Error::MissingStream { path } => write!(f, r#"Stream "{}" not found"#, path),
+            Error::InvalidTextOffset { module, offset } => write!(
+                f,
+                r#"Module "{}": no source code found at offset {}"#,
+                module, offset
+            ),
+            Error::UnsupportedFormat(format) => write!(f, "Unsupported file format: {}", format),
         }
     }
 }
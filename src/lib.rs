@@ -4,7 +4,7 @@
 //! Structure][MS-OVBA] protocol (Revision 9.1, published 2020-02-19).
 //!
 //! The main entry point into the API is the [`Project`] type, returned by the
-//! [`open_project`] function.
+//! [`open_project`] and [`open_project_from_reader`] functions.
 //!
 //! # Usage
 //!
@@ -50,6 +50,18 @@
 //! # Ok::<(), ovba::Error>(())
 //! ```
 //!
+//! # WebAssembly
+//!
+//! Parsing is pure computation, and the crate builds for the `wasm32-unknown-unknown`
+//! target. Projects are opened from memory using [`open_project`]; only
+//! [`Project::open_file`] requires file system access.
+//!
+//! # Identifiers
+//!
+//! VBA identifiers are case-insensitive. Consequently, all functions that look up
+//! modules by name compare names ignoring ASCII case (see [`Module::name_eq`]), and so
+//! does [`Reference::name_eq`].
+//!
 //! [MS-OVBA]: https://docs.microsoft.com/en-us/openspecs/office_file_formats/ms-ovba/575462ba-bf67-4190-9fac-c275523c75fc
 //! [MS-CFB]: https://docs.microsoft.com/en-us/openspecs/windows_protocols/ms-cfb/53989ce4-7b05-4f8d-829b-d08d6148375b
 
@@ -59,36 +71,185 @@
 mod error;
 pub use crate::error::{Error, Result};
 
+mod hash;
 mod parser;
+mod source;
+mod writer;
 
 use cfb::CompoundFile;
-use parser::cp_to_string;
+use parser::{cp_to_string, string_to_cp};
 
 use std::{
     cell::RefCell,
-    io::{Cursor, Read},
+    fmt,
+    io::{Cursor, Read, Seek, SeekFrom, Write},
     path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
+// Path of the *dir* stream, relative to the project root.
+const DIR_STREAM_PATH: &str = "/VBA/dir";
+
+// Known locations of the project root. Office documents in the binary file formats
+// embed the VBA project in a storage, e.g. */Macros* (Word) or */_VBA_PROJECT_CUR*
+// (Excel).
+const PROJECT_ROOTS: &[&str] = &["", "/Macros", "/_VBA_PROJECT_CUR"];
+
+// Names of procedures that Office applications run automatically.
+const AUTO_EXEC_PROCEDURES: &[&str] = &[
+    "AutoExec",
+    "AutoOpen",
+    "AutoNew",
+    "AutoClose",
+    "AutoExit",
+    "Auto_Open",
+    "Auto_Close",
+    "Document_Open",
+    "Document_New",
+    "Document_Close",
+    "DocumentOpen",
+    "Workbook_Open",
+    "Workbook_Activate",
+    "Workbook_BeforeClose",
+];
+
+// Keys of the *PROJECT* stream that name a module.
+const MODULE_KEYS: &[&str] = &["Document", "Module", "Class", "BaseClass"];
+
 /// Represents a VBA project.
 ///
 /// This type serves as the entry point into this crate's functionality and exposes the
 /// public API surface.
-pub struct Project {
+///
+/// The type parameter `F` is the reader backing the CFB container. It defaults to an
+/// in-memory buffer, as used by [`open_project`]. Any other reader that implements
+/// `Read + Seek` can be used through [`open_project_from_reader`].
+pub struct Project<F = Cursor<Vec<u8>>> {
     /// Specifies version-independent information for the VBA project.
     pub information: Information,
     /// Specifies the external references of the VBA project.
     pub references: Vec<Reference>,
     /// Specifies the modules in the project.
     pub modules: Vec<Module>,
-    // TODO: Figure out how to make this generic (attempts have failed with
-    //       trait bound violations). This would allow [`open_project`] to
-    //       accept a wider range of input types.
-    container: RefCell<CompoundFile<Cursor<Vec<u8>>>>,
+    container: RefCell<CompoundFile<F>>,
+    // Path of the storage holding the *PROJECT* stream and the *VBA* storage, without a
+    // trailing `/`. This is empty if the VBA project is stored at the CFB's root.
+    root: String,
+    options: ProjectOptions,
+    // The input of `open_project` and friends, see `Project::raw_bytes`.
+    raw: Vec<u8>,
+}
+
+/// Specifies options that control how a VBA project is parsed.
+///
+/// The default is strict parsing, which enforces the file format specification.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Enables lenient parsing.
+    ///
+    /// When set, records with unknown IDs in the *dir* stream are skipped, the declared
+    /// module count isn't checked against the MODULE Records, and trailing data
+    /// following compressed containers and the *dir* stream is ignored. This allows
+    /// extracting as much information as possible from projects that deviate from the
+    /// specification.
+    pub lenient: bool,
+}
+
+/// Builds the configuration used to open a VBA project.
+///
+/// This covers the [`ParseOptions`] as well as settings that affect the [`Project`]
+/// after it has been opened. The defaults match [`open_project`].
+///
+/// ```rust,no_run
+/// use std::fs::read;
+/// use ovba::ProjectOptions;
+///
+/// let data = read("vbaProject.bin")?;
+/// let project = ProjectOptions::new()
+///     .lenient(true)
+///     .max_decompressed(16 * 1024 * 1024)
+///     .open(data)?;
+/// # Ok::<(), ovba::Error>(())
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ProjectOptions {
+    parse: ParseOptions,
+    max_decompressed: Option<usize>,
+    code_page_override: Option<u16>,
+}
+
+impl ProjectOptions {
+    /// Creates the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enables or disables lenient parsing; see [`ParseOptions::lenient`].
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.parse.lenient = lenient;
+        self
+    }
+
+    /// Limits the size of any decompressed stream to `max_len` bytes.
+    ///
+    /// This applies to the *dir* stream while opening the project, and to module source
+    /// code afterwards. Exceeding the limit is reported as an [`Error::Decompressor`].
+    /// By default, the size is not limited.
+    pub fn max_decompressed(mut self, max_len: usize) -> Self {
+        self.max_decompressed = Some(max_len);
+        self
+    }
+
+    /// Decodes text using `code_page` instead of the code page declared in the
+    /// PROJECTCODEPAGE Record, if set.
+    ///
+    /// This helps with projects that declare a wrong or invalid code page (such as 0).
+    /// The override applies to all MBCS strings in the *dir* stream and to module
+    /// source code. [`Information::code_page`] reports the override, which is also
+    /// written when saving the project. Unsupported code pages fail with
+    /// [`Error::UnsupportedCodePage`].
+    pub fn code_page_override(mut self, code_page: Option<u16>) -> Self {
+        self.code_page_override = code_page;
+        self
+    }
+
+    /// Opens a VBA project from `raw`, as described for [`open_project`].
+    pub fn open(self, raw: Vec<u8>) -> Result<Project> {
+        open_bytes(raw, self)
+    }
+
+    /// Opens a VBA project from a reader, as described for [`open_project_from_reader`].
+    pub fn open_reader<R>(self, reader: R) -> Result<Project<R>>
+    where
+        R: Read + Seek,
+    {
+        open(reader, self)
+    }
+
+    /// Decompresses a CompressedContainer, honoring the parse options and size limit.
+    fn decompress(&self, compressed: &[u8]) -> Result<Vec<u8>> {
+        let max_len = self.max_decompressed.unwrap_or(usize::MAX);
+        if self.parse.lenient {
+            let (_, data) = parser::decompress_partial_limited(compressed, max_len)
+                .map_err(|e| parser::to_decompressor_error(e, compressed))?;
+            Ok(data)
+        } else {
+            decompress_limited(compressed, max_len)
+        }
+    }
+}
+
+impl From<ParseOptions> for ProjectOptions {
+    fn from(parse: ParseOptions) -> Self {
+        Self {
+            parse,
+            ..Self::default()
+        }
+    }
 }
 
 /// Specifies the platform for which the VBA project is created.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SysKind {
     /// For 16-bit Windows Platforms.
     Win16,
@@ -100,10 +261,20 @@ pub enum SysKind {
     Win64,
 }
 
-// TODO: Remove exemption once the implementation is complete.
-#[allow(dead_code)]
+impl fmt::Display for SysKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            SysKind::Win16 => "16-bit Windows",
+            SysKind::Win32 => "32-bit Windows",
+            SysKind::MacOs => "Macintosh",
+            SysKind::Win64 => "64-bit Windows",
+        };
+        f.write_str(label)
+    }
+}
+
 /// Specifies a reference to a twiddled type library and its extended type library.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ReferenceControl {
     /// (Optional) Name entry
     name: Option<String>,
@@ -111,36 +282,30 @@ pub struct ReferenceControl {
     libid_twiddled: String,
     name_extended: Option<String>,
     libid_extended: String,
-    guid: Vec<u8>, // Should be an `[u8; 16]`, though I'm not sure how to convert &[u8] returned by the parser into an array.
+    guid: [u8; 16],
     /// MUST be Unique for each `ReferenceControl` in the VBA projectwith the same
     /// libid_original.
     cookie: u32,
 }
 
-// TODO: Remove exemption once the implementation is complete.
-#[allow(dead_code)]
 /// Specifies the identifier of the Automation type library the containing
 /// [`ReferenceControl`]'s twiddled type library was generated from.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ReferenceOriginal {
     /// (Optional) Name entry
     name: Option<String>,
     libid_original: String,
 }
 
-// TODO: Remove exemption once the implementation is complete.
-#[allow(dead_code)]
 /// Specifies a reference to an Automation type library.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ReferenceRegistered {
     name: Option<String>,
     libid: String,
 }
 
-// TODO: Remove exemption once the implementation is complete.
-#[allow(dead_code)]
 /// Specifies a reference to an external VBA project.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ReferenceProject {
     name: Option<String>,
     libid_absolute: String,
@@ -149,8 +314,117 @@ pub struct ReferenceProject {
     minor_version: u16,
 }
 
+impl ReferenceControl {
+    /// Returns the name of the `ReferenceControl`, if present.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the LIBID of the Automation type library the twiddled type library was
+    /// generated from, if present.
+    pub fn libid_original(&self) -> Option<&str> {
+        self.libid_original.as_deref()
+    }
+
+    /// Returns the LIBID of the twiddled type library.
+    pub fn libid_twiddled(&self) -> &str {
+        &self.libid_twiddled
+    }
+
+    /// Returns the name of the extended type library, if present.
+    pub fn name_extended(&self) -> Option<&str> {
+        self.name_extended.as_deref()
+    }
+
+    /// Returns the LIBID of the extended type library.
+    pub fn libid_extended(&self) -> &str {
+        &self.libid_extended
+    }
+
+    /// Returns the GUID of the Automation type library the extended type library was
+    /// generated from.
+    ///
+    /// The value is returned as stored, i.e. the `Data1`, `Data2`, and `Data3` fields
+    /// are encoded in little-endian byte order.
+    pub fn guid(&self) -> &[u8; 16] {
+        &self.guid
+    }
+
+    /// Returns the GUID in its canonical string representation, e.g.
+    /// `{0D452EE1-E08F-101A-852E-02608C4D0BB4}`.
+    pub fn guid_string(&self) -> String {
+        let g = &self.guid;
+        format!(
+            "{{{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}}}",
+            u32::from_le_bytes([g[0], g[1], g[2], g[3]]),
+            u16::from_le_bytes([g[4], g[5]]),
+            u16::from_le_bytes([g[6], g[7]]),
+            g[8],
+            g[9],
+            g[10],
+            g[11],
+            g[12],
+            g[13],
+            g[14],
+            g[15]
+        )
+    }
+
+    /// Returns the cookie of the extended type library.
+    pub fn cookie(&self) -> u32 {
+        self.cookie
+    }
+}
+
+impl ReferenceOriginal {
+    /// Returns the name of the `ReferenceOriginal`, if present.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the LIBID of the Automation type library.
+    pub fn libid_original(&self) -> &str {
+        &self.libid_original
+    }
+}
+
+impl ReferenceRegistered {
+    /// Returns the name of the `ReferenceRegistered`, if present.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the LIBID of the Automation type library.
+    pub fn libid(&self) -> &str {
+        &self.libid
+    }
+}
+
+impl ReferenceProject {
+    /// Returns the name of the `ReferenceProject`, if present.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Returns the absolute path to the referenced VBA project.
+    pub fn libid_absolute(&self) -> &str {
+        &self.libid_absolute
+    }
+
+    /// Returns the path to the referenced VBA project, relative to the referencing
+    /// VBA project.
+    pub fn libid_relative(&self) -> &str {
+        &self.libid_relative
+    }
+
+    /// Returns the version of the referenced VBA project as a `(major, minor)` tuple.
+    pub fn version(&self) -> (u32, u16) {
+        (self.major_version, self.minor_version)
+    }
+}
+
 /// Specifies a reference to an Automation type library or VBA project.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Reference {
     /// The `Reference` is a [`ReferenceControl`].
     Control(ReferenceControl),
@@ -162,13 +436,110 @@ pub enum Reference {
     Project(ReferenceProject),
 }
 
-// TODO: Remove exemption once the implementation is complete.
-#[allow(dead_code)]
+impl Reference {
+    /// Returns the name of the `Reference`, if present.
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Reference::Control(value) => value.name(),
+            Reference::Original(value) => value.name(),
+            Reference::Registered(value) => value.name(),
+            Reference::Project(value) => value.name(),
+        }
+    }
+
+    /// Returns whether the `Reference` is named `name`, ignoring ASCII case.
+    pub fn name_eq(&self, name: &str) -> bool {
+        self.name()
+            .map(|n| n.eq_ignore_ascii_case(name))
+            .unwrap_or_default()
+    }
+}
+
+/// Specifies the differences between the modules of two projects, see [`Project::diff`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ProjectDiff {
+    /// Specifies the names of modules only present in the other project.
+    pub added: Vec<String>,
+    /// Specifies the names of modules only present in this project.
+    pub removed: Vec<String>,
+    /// Specifies the modules present in both projects that differ.
+    pub modified: Vec<ModuleDiff>,
+}
+
+impl ProjectDiff {
+    /// Returns whether both projects' modules are the same.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// Specifies how a module differs between two projects, see [`Project::diff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleDiff {
+    /// Specifies the module's name in this project.
+    pub name: String,
+    /// Specifies whether the module's source code differs.
+    pub source_changed: bool,
+    /// Specifies whether the module's metadata differs.
+    pub metadata_changed: bool,
+}
+
+/// Specifies whether an entry in the CFB is a storage or a stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    /// A storage, which holds other storages and streams, similar to a directory.
+    Storage,
+    /// A stream, which holds data, similar to a file.
+    Stream,
+}
+
+/// Specifies an entry in the CFB, see [`Project::list_entries`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Specifies the entry's name.
+    pub name: String,
+    /// Specifies the entry's path inside the CFB.
+    pub path: String,
+    /// Specifies whether the entry is a storage or a stream.
+    pub kind: EntryKind,
+}
+
+/// Specifies a storage in the CFB along with its children, see [`Project::tree`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageNode {
+    /// Specifies the storage's name.
+    pub name: String,
+    /// Specifies the storage's path inside the CFB.
+    pub path: String,
+    /// Specifies the storages contained in this storage.
+    pub storages: Vec<StorageNode>,
+    /// Specifies the streams contained in this storage.
+    pub streams: Vec<Entry>,
+}
+
+bitflags::bitflags! {
+    /// Specifies the `LIBFLAGS` of an Automation type library, as defined in MS-OAUT
+    /// section 2.2.20.
+    pub struct LibFlags: u32 {
+        /// The type library is restricted, and MUST NOT be displayed to users.
+        const RESTRICTED = 0x0000_0001;
+        /// The type library describes controls, and MUST NOT be displayed in type
+        /// browsers intended for nonvisual objects.
+        const CONTROL = 0x0000_0002;
+        /// The type library MUST NOT be displayed to users, although its use is not
+        /// restricted.
+        const HIDDEN = 0x0000_0004;
+        /// The type library exists in a persisted form on disk.
+        const HAS_DISK_IMAGE = 0x0000_0008;
+    }
+}
+
 /// Specifies version-independent information for the VBA project.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Information {
     /// Specifies the platform for which the VBA project is created.
     pub sys_kind: SysKind,
+    compat_version: Option<u32>,
     lcid: u32,
     lcid_invoke: u32,
     /// Specifies the code page for the VBA project.
@@ -176,16 +547,114 @@ pub struct Information {
     pub code_page: u16,
     name: String,
     doc_string: String,
+    doc_string_unicode: String,
     help_file_1: String,
     help_context: u32,
     lib_flags: u32,
     version_major: u32,
     version_minor: u16,
     constants: String,
+    constants_unicode: String,
+}
+
+impl Information {
+    /// Returns the compatibility version of the VBA project, if present.
+    ///
+    /// The PROJECTCOMPATVERSION Record is written by newer versions of Office; older
+    /// versions omit it.
+    pub fn compat_version(&self) -> Option<u32> {
+        self.compat_version
+    }
+
+    /// Returns the LCID of the VBA project.
+    pub fn lcid(&self) -> u32 {
+        self.lcid
+    }
+
+    /// Returns the LCID used to invoke Automation servers from the VBA project.
+    pub fn lcid_invoke(&self) -> u32 {
+        self.lcid_invoke
+    }
+
+    /// Returns the name of the VBA project.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the description of the VBA project.
+    pub fn doc_string(&self) -> &str {
+        &self.doc_string
+    }
+
+    /// Returns the description of the VBA project, preferring its Unicode variant.
+    ///
+    /// The *dir* stream stores the description encoded using the project's code page,
+    /// optionally followed by its UTF-16 encoding. The latter is returned if present
+    /// and non-empty, as it can represent all characters. Otherwise, this is the same as
+    /// [`Information::doc_string`].
+    pub fn doc_string_best(&self) -> &str {
+        prefer_unicode(&self.doc_string_unicode, &self.doc_string)
+    }
+
+    /// Returns the path to the Help file of the VBA project.
+    ///
+    /// The Help file is shared by the project and all of its modules. An empty path
+    /// indicates that the project doesn't have a Help file. The *dir* stream stores
+    /// this path twice (HelpFile1 and HelpFile2); the copies MUST be identical.
+    pub fn help_file(&self) -> &str {
+        &self.help_file_1
+    }
+
+    /// Returns the Help topic identifier of the VBA project.
+    ///
+    /// This identifies a topic in the [`Information::help_file`]. Modules carry their
+    /// own topic identifier in [`Module::help_context`].
+    pub fn help_context(&self) -> u32 {
+        self.help_context
+    }
+
+    /// Returns the `LIBFLAGS` of the VBA project's Automation type library.
+    pub fn lib_flags(&self) -> u32 {
+        self.lib_flags
+    }
+
+    /// Returns the `LIBFLAGS` of the VBA project's Automation type library as
+    /// [`LibFlags`].
+    ///
+    /// MS-OVBA requires the value to be 0. Bits not defined by [`LibFlags`] are dropped;
+    /// use [`Information::lib_flags`] to get the raw value.
+    pub fn library_flags(&self) -> LibFlags {
+        LibFlags::from_bits_truncate(self.lib_flags)
+    }
+
+    /// Returns the version of the VBA project as a `(major, minor)` tuple.
+    pub fn version(&self) -> (u32, u16) {
+        (self.version_major, self.version_minor)
+    }
+
+    /// Returns the compilation constants of the VBA project.
+    pub fn constants(&self) -> &str {
+        &self.constants
+    }
+
+    /// Returns the compilation constants of the VBA project, preferring their Unicode
+    /// variant; see [`Information::doc_string_best`].
+    pub fn constants_best(&self) -> &str {
+        prefer_unicode(&self.constants_unicode, &self.constants)
+    }
+
+    /// Returns the compilation constants of the VBA project as (name, value) pairs.
+    ///
+    /// This splits [`Information::constants`], e.g. `DEBUG = 1 : TRACE = -1`, into its
+    /// individual declarations. Values are reported as written; VBA only permits integer
+    /// values.
+    pub fn conditional_constants(&self) -> Vec<(String, String)> {
+        parser::parse_conditional_constants(&self.constants)
+    }
 }
 
 /// Specifies the containing module's type.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ModuleType {
     /// Specifies a procedural module.
     ///
@@ -209,20 +678,82 @@ pub enum ModuleType {
     DocClsDesigner,
 }
 
+impl fmt::Display for ModuleType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            ModuleType::Procedural => "Standard Module",
+            ModuleType::DocClsDesigner => "Document, Class, or Designer Module",
+        };
+        f.write_str(label)
+    }
+}
+
+/// Specifies the kind of a module.
+///
+/// Unlike [`ModuleType`], this distinguishes document, class, and designer modules. The
+/// information is not available from the *dir* stream, and is determined from the
+/// *PROJECT* stream instead (see [`Project::module_kind`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModuleKind {
+    /// Specifies a procedural module.
+    Procedural,
+    /// Specifies a document module.
+    Document,
+    /// Specifies a class module.
+    Class,
+    /// Specifies a designer module.
+    Designer,
+}
+
+impl ModuleKind {
+    /// Returns the file extension used by the VBA editor when exporting a module of this
+    /// kind, see [`Project::export_module`].
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            ModuleKind::Procedural => "bas",
+            ModuleKind::Document | ModuleKind::Class => "cls",
+            ModuleKind::Designer => "frm",
+        }
+    }
+}
+
+impl fmt::Display for ModuleKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            ModuleKind::Procedural => "Standard Module",
+            ModuleKind::Document => "Document Module",
+            ModuleKind::Class => "Class Module",
+            ModuleKind::Designer => "Designer Module",
+        };
+        f.write_str(label)
+    }
+}
+
 /// Specifies data for a module.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Module {
     /// Specifies a VBA identifier as the name of the containing `Module`.
     pub name: String,
+    // The UTF-16 encoding of `name`, if present in the *dir* stream. This is used as an
+    // alternative when looking up modules by name.
+    name_unicode: String,
     /// Specifies the stream name in the VBA storage corresponding to the containing
     /// `Module`.
     pub stream_name: String,
+    // The UTF-16 encoding of `stream_name`. This is used as a fallback when looking up
+    // the module stream, in case `stream_name` doesn't survive the MBCS round-trip.
+    stream_name_unicode: String,
     /// Specifies the description for the containing `Module`.
     pub doc_string: String,
+    // The UTF-16 encoding of `doc_string`, if present in the *dir* stream.
+    doc_string_unicode: String,
     /// Specifies the location of the source code within the stream that corresponds to
     /// the containing `Module`.
     pub text_offset: usize,
     /// Specifies the Help topic identifier for the containing `Module`.
+    ///
+    /// The topic is located in the project's Help file, see [`Information::help_file`]
+    /// and [`Project::help_topic`].
     pub help_context: u32,
     /// Specifies whether the containing `Module` is a procedural module, document
     /// module, class module, or designer module.
@@ -234,143 +765,2122 @@ pub struct Module {
     pub private: bool,
 }
 
-impl Project {
-    /// Returns a stream's decompressed data.
+impl Module {
+    /// Returns whether the module is named `name`.
     ///
-    /// This function reads a stream referenced by `stream_path` and passes the data
-    /// starting at `offset` into the RLE decompressor.
-    ///
-    /// The primary use case for this function is to extract source code from VBA
-    /// [`Module`]s. The respective `offset` is reported by [`Module::text_offset`].
-    ///
-    /// This is a low-level function that is useful for very specific use cases only.
-    /// Client code that needs to read source code should use [`Project::module_source`]
-    /// or [`Project::module_source_raw`] instead.
-    // TODO: Code example
-    pub fn decompress_stream_from<P>(&self, stream_path: P, offset: usize) -> Result<Vec<u8>>
-    where
-        P: AsRef<Path>,
-    {
-        let data = self.read_stream(stream_path)?;
-        let data = parser::decompress(&data[offset..])
-            .map_err(|_| Error::Decompressor)?
-            .1;
-        Ok(data)
+    /// Following the rules for VBA identifiers, names are compared ignoring ASCII case.
+    /// Both the MBCS name and its Unicode variant (if present in the *dir* stream) are
+    /// considered.
+    pub fn name_eq(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name) || self.name_unicode.eq_ignore_ascii_case(name)
     }
 
-    // TODO: This should probably live someplace else. It exposes information internal to
-    //       the CFB implementation, that's not *immediately* useful or related to this
-    //       library's primary responsibility.
-
-    /// Returns a list of entries (storages and streams) in the raw binary data. Each
-    /// entry is represented as a tuple of two `String`s, where the first element
-    /// contains the entry's name and the second element the entry's path inside the
-    /// CFB.
+    /// Returns the module's name, preferring its Unicode variant.
     ///
-    /// The raw binary data is encoded as a [Compound File Binary][MS-CFB]
-    ///
-    /// [MS-CFB]: https://docs.microsoft.com/en-us/openspecs/windows_protocols/ms-cfb/53989ce4-7b05-4f8d-829b-d08d6148375b
-    pub fn list(&self) -> Result<Vec<(String, String)>> {
-        let mut result = Vec::new();
-        for entry in self
-            .container
-            .borrow()
-            .walk_storage("/")
-            .map_err(Error::Cfb)?
-        {
-            result.push((
-                entry.name().to_owned(),
-                entry.path().to_str().unwrap_or_default().to_owned(),
-            ));
-        }
-        Ok(result)
+    /// This and the other `*_best` functions return the UTF-16 variant stored in the
+    /// *dir* stream if non-empty, and the public (MBCS) field otherwise. The Unicode
+    /// variants reflect the *dir* stream as read, and don't track changes to the public
+    /// fields. [`Project::update_dir_stream`] writes them back, unless the respective
+    /// public field has been changed.
+    pub fn name_best(&self) -> &str {
+        prefer_unicode(&self.name_unicode, &self.name)
     }
 
-    /// Returns a module's source code.
-    ///
-    /// Similar to [`Project::module_source_raw`] this function returns the source code
-    /// of a project's module. After the raw source code has been decoded it is then
-    /// converted to a `String` using the project's code page.
-    pub fn module_source(&self, name: &str) -> Result<String> {
-        let source_raw = self.module_source_raw(name)?;
-        let source = cp_to_string(&source_raw, self.information.code_page);
+    /// Returns the module's stream name, preferring its Unicode variant.
+    pub fn stream_name_best(&self) -> &str {
+        prefer_unicode(&self.stream_name_unicode, &self.stream_name)
+    }
 
-        Ok(source)
+    /// Returns the module's description, preferring its Unicode variant.
+    pub fn doc_string_best(&self) -> &str {
+        prefer_unicode(&self.doc_string_unicode, &self.doc_string)
     }
+}
 
-    /// Returns the raw source code from a module.
+/// Specifies the project properties stored in the *PROJECT* stream.
+///
+/// The *PROJECT* stream is a text stream made up of `Key=Value` lines, encoded using the
+/// project's code page. Values enclosed in double quotes are reported without the
+/// quotes.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ProjectProperties {
+    /// Specifies the class identifier (`ID=`) of the VBA project's Automation type
+    /// library.
+    pub id: Option<String>,
+    /// Specifies the names of all document modules (`Document=`).
+    pub document_modules: Vec<String>,
+    /// Specifies the names of all procedural modules (`Module=`).
+    pub procedural_modules: Vec<String>,
+    /// Specifies the names of all class modules (`Class=`).
+    pub class_modules: Vec<String>,
+    /// Specifies the names of all designer modules (`BaseClass=`).
+    pub designer_modules: Vec<String>,
+    /// Specifies the class identifiers of all ActiveX controls used by designer modules
+    /// (`Package=`).
+    pub packages: Vec<String>,
+    /// Specifies the path to the Help file (`HelpFile=`).
+    pub help_file: Option<String>,
+    /// Specifies the path of the file generated when the VBA project is compiled
+    /// (`ExeName32=`).
+    pub exe_name_32: Option<String>,
+    /// Specifies the name of the VBA project (`Name=`).
+    pub name: Option<String>,
+    /// Specifies the Help topic identifier (`HelpContextID=`).
+    pub help_context_id: Option<String>,
+    /// Specifies the description of the VBA project (`Description=`).
+    pub description: Option<String>,
+    /// Specifies the VBA compatibility version (`VersionCompatible32=`).
+    pub version_compatible_32: Option<String>,
+    /// Specifies the encrypted protection state (`CMG=`).
+    pub protection_state: Option<String>,
+    /// Specifies the encrypted password protection (`DPB=`).
+    pub password: Option<String>,
+    /// Specifies the encrypted visibility state (`GC=`).
+    pub visibility_state: Option<String>,
+    /// Specifies the `Key=Value` pairs of the `[Host Extender Info]` section.
+    pub host_extenders: Vec<(String, String)>,
+    /// Specifies the `Key=Value` pairs of the `[Workspace]` section.
+    pub workspace: Vec<(String, String)>,
+    /// Specifies all `Key=Value` pairs that precede the `[Host Extender Info]` section,
+    /// in order of appearance. This includes keys not represented by any of the other
+    /// fields.
+    pub properties: Vec<(String, String)>,
+}
+
+/// Specifies the decrypted protection properties of the *PROJECT* stream, see
+/// [`Project::protection`].
+///
+/// Each field is `None` if the respective property is missing.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Protection {
+    /// Specifies the ProjectProtectionState (`CMG=`), a 32-bit little-endian bit field.
     ///
-    /// The result contains a module's source code as is. No character encoding conversion
-    /// is done. The data is encoded using the project's code page available through
-    /// [`Information::code_page`].
-    pub fn module_source_raw(&self, name: &str) -> Result<Vec<u8>> {
-        let module = self
-            .modules
-            .iter()
-            .find(|&module| module.name == name)
-            .ok_or_else(|| Error::ModuleNotFound(name.to_owned()))?;
+    /// Bit 0 (`fUserProtected`) is set if the user locked the project for viewing, and
+    /// bits 1 (`fHostProtected`) and 2 (`fVBEProtected`) are set if the host application
+    /// or the VBA editor, respectively, protect the project.
+    pub protection_state: Option<Vec<u8>>,
+    /// Specifies the ProjectPassword (`DPB=`).
+    ///
+    /// This is a single `0x00` byte if the project has no password. Otherwise, it holds
+    /// a 29-byte structure with a 4-byte salt (`Key`) and the SHA-1 hash of the
+    /// password and salt (`PasswordHash`). Neither field may contain `0x00` bytes; the
+    /// structure's `GrbitKey` and `GrbitHashNull` fields mark the bytes that are
+    /// actually `0x00`. Some projects store the password in plain text instead.
+    pub password: Option<Vec<u8>>,
+    /// Specifies the ProjectVisibilityState (`GC=`).
+    ///
+    /// This is a single byte: `0x00` if the project is locked for viewing, and `0xFF`
+    /// otherwise.
+    pub visibility_state: Option<Vec<u8>>,
+}
 
-        let path = format!("/VBA\\{}", &module.stream_name);
-        let offset = module.text_offset;
-        let src_code = self.decompress_stream_from(path, offset)?;
+/// Specifies information for the VBA project, including project information, project
+/// references, and modules.
+///
+/// This is the result of parsing a *dir* stream through [`parse_dir_stream`]. A
+/// [`Project`] exposes the same information through its fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectInformation {
+    /// Specifies version-independent information for the VBA project.
+    pub information: Information,
+    /// Specifies the external references of the VBA project.
+    pub references: Vec<Reference>,
+    /// Specifies the modules in the project.
+    pub modules: Vec<Module>,
+}
 
-        Ok(src_code)
-    }
+/// Specifies the scheme of a [`DigitalSignature`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureKind {
+    /// The signature is stored in the `\x05DigitalSignature` stream.
+    Legacy,
+    /// The signature is stored in the `\x05DigitalSignatureAgile` stream.
+    Agile,
+    /// The signature is stored in the `\x05DigitalSignatureV3` stream.
+    V3,
+}
 
-    /// Returns a stream's contents.
-    ///
-    /// This is a low-level function operating on the CFB data. The CFB is the storage
-    /// container of the raw binary VBA project.
-    pub fn read_stream<P>(&self, stream_path: P) -> Result<Vec<u8>>
-    where
-        P: AsRef<Path>,
-    {
-        let mut buffer = Vec::new();
-        self.container
-            .borrow_mut()
-            .open_stream(stream_path)
-            .map_err(Error::Cfb)?
-            .read_to_end(&mut buffer)
-            .map_err(Error::Cfb)?;
+/// Specifies the hash algorithm used to compute a content hash, see
+/// [`Project::content_hash`].
+///
+/// The algorithm is recorded in the signature. The legacy scheme commonly uses MD5, the
+/// other schemes SHA-1 or SHA-256.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    /// MD5, producing a 16-byte digest.
+    Md5,
+    /// SHA-1, producing a 20-byte digest.
+    Sha1,
+    /// SHA-256, producing a 32-byte digest.
+    Sha256,
+}
 
-        Ok(buffer)
+/// Specifies the digital signature of a VBA project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigitalSignature {
+    /// Specifies the signature scheme.
+    pub kind: SignatureKind,
+    /// Specifies the signature stream's data as is.
+    pub data: Vec<u8>,
+}
+
+impl DigitalSignature {
+    /// Returns the PKCS #7 signature.
+    ///
+    /// The signature is located through the DigSigInfoSerialized structure at the start
+    /// of the stream. Returns `None` if the stream data doesn't follow this layout.
+    pub fn pkcs7(&self) -> Option<&[u8]> {
+        let (_, (size, offset)) = parser::parse_signature_location(&self.data).ok()?;
+        self.data.get(offset..offset.checked_add(size)?)
     }
 }
 
-/// Opens a VBA project.
+/// Specifies the kind of a [`Procedure`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcedureKind {
+    /// A `Sub` procedure.
+    Sub,
+    /// A `Function` procedure.
+    Function,
+    /// A `Property Get` procedure.
+    PropertyGet,
+    /// A `Property Let` procedure.
+    PropertyLet,
+    /// A `Property Set` procedure.
+    PropertySet,
+}
+
+/// Specifies the visibility of a [`Procedure`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visibility {
+    /// The procedure is declared `Public`, or without a scope modifier.
+    Public,
+    /// The procedure is declared `Private`.
+    Private,
+    /// The procedure is declared `Friend`.
+    Friend,
+}
+
+/// Specifies a procedure declared in a module's source code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Procedure {
+    /// Specifies the procedure's name.
+    pub name: String,
+    /// Specifies the kind of procedure.
+    pub kind: ProcedureKind,
+    /// Specifies the procedure's visibility.
+    pub visibility: Visibility,
+    /// Specifies the (1-based) line number of the procedure's declaration.
+    pub start_line: usize,
+    /// Specifies the (1-based) line number of the procedure's `End` statement.
+    pub end_line: usize,
+}
+
+/// Specifies a module's source code, split into its leading attributes and code body.
+///
+/// Module source commonly starts with `Attribute` lines such as
+/// `Attribute VB_Name = "Module1"` or `Attribute VB_PredeclaredId = True`. These are
+/// hidden in the VBA editor, but describe properties of the module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleSource {
+    /// Specifies the leading attributes as (name, value) pairs, in order of appearance.
+    ///
+    /// Quotes surrounding string values are removed.
+    pub attributes: Vec<(String, String)>,
+    /// Specifies the source code following the leading attributes.
+    pub code: String,
+}
+
+impl ModuleSource {
+    /// Returns the value of the attribute called `name`, compared case-insensitively.
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the code body with all line endings (CRLF, CR, or LF) converted to LF.
+    ///
+    /// This is the input to [`Project::module_source_hash`].
+    pub fn normalized_code(&self) -> String {
+        source::normalize_line_endings(&self.code)
+    }
+}
+
+impl From<&str> for ModuleSource {
+    fn from(source: &str) -> Self {
+        source::split_attributes(source)
+    }
+}
+
+/// Specifies the header information of the *_VBA_PROJECT* stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VbaVersion {
+    /// Specifies the version of VBA used to create the VBA project.
+    ///
+    /// The value 0xFFFF is written by tools that don't produce a PerformanceCache.
+    pub version: u16,
+    /// Specifies whether the stream contains a PerformanceCache, i.e. compiled, p-code
+    /// representations of the modules' source code.
+    ///
+    /// The PerformanceCache is version-dependent, and may not match the source code.
+    pub performance_cache: bool,
+}
+
+/// Specifies how [`Project::set_module_source_with`] treats the PerformanceCache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheMode {
+    /// The PerformanceCache preceding the module's source code is preserved.
+    Keep,
+    /// The PerformanceCache of all modules is discarded, see
+    /// [`Project::discard_performance_cache`].
+    Discard,
+}
+
+/// Specifies an overview of a VBA project, see [`Project::summary`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectSummary {
+    /// Specifies the project's name.
+    pub name: String,
+    /// Specifies the platform for which the project was created.
+    pub sys_kind: SysKind,
+    /// Specifies the project's code page.
+    pub code_page: u16,
+    /// Specifies the number of modules.
+    pub module_count: usize,
+    /// Specifies the number of references.
+    pub reference_count: usize,
+    /// Specifies whether the project is digitally signed.
+    pub has_signature: bool,
+    /// Specifies whether the project is locked for viewing, see [`Project::is_locked`],
+    /// or `None` if the *PROJECT* stream is missing or its protection state cannot be
+    /// decrypted.
+    pub is_locked: Option<bool>,
+    /// Specifies the version of VBA used to create the project, or `None` if the
+    /// *_VBA_PROJECT* stream is missing or malformed.
+    pub vba_version: Option<u16>,
+}
+
+/// Specifies a description of a VBA project, see [`Project::manifest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    /// Specifies the project's information, see [`Project::information`].
+    pub information: Information,
+    /// Specifies the project's references, see [`Project::references`].
+    pub references: Vec<Reference>,
+    /// Specifies the project's modules.
+    pub modules: Vec<ModuleManifest>,
+    /// Specifies the scheme of the project's digital signature, or `None` if it isn't
+    /// signed.
+    pub signature: Option<SignatureKind>,
+    /// Specifies whether the project is locked for viewing, see [`Project::is_locked`],
+    /// or `None` if the *PROJECT* stream is missing or its protection state cannot be
+    /// decrypted.
+    pub is_locked: Option<bool>,
+}
+
+/// Specifies a module in a [`Manifest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleManifest {
+    /// Specifies the module's name.
+    pub name: String,
+    /// Specifies the module's kind, see [`Project::module_kind`], or `None` if the
+    /// *PROJECT* stream is missing or malformed.
+    pub kind: Option<ModuleKind>,
+    /// Specifies the path of the module's stream.
+    pub stream_path: String,
+    /// Specifies the SHA-256 digest of the module's source code, see
+    /// [`Project::module_source_hash`], or `None` if the source code cannot be read.
+    pub source_hash: Option<Vec<u8>>,
+    /// Specifies the size of the module's stream in bytes, see
+    /// [`Project::module_stream_size`], or `None` if the stream is missing.
+    pub stream_size: Option<u64>,
+}
+
+impl<F> Project<F>
+where
+    F: Read + Seek,
+{
+    /// Returns all procedures that are run automatically by Office applications.
+    ///
+    /// Each procedure is reported as a tuple of the module name and the procedure name.
+    /// Procedures are identified by scanning the source code of all modules (see
+    /// [`parse_procedures`]) for declarations with well-known names, such as `AutoOpen`,
+    /// `Document_Open`, or `Workbook_Open`. Names are compared case-insensitively.
+    pub fn auto_exec_procedures(&self) -> Result<Vec<(String, String)>> {
+        let mut result = Vec::new();
+        for (module, source) in self.iter_modules()? {
+            for procedure in parse_procedures(&source) {
+                if AUTO_EXEC_PROCEDURES
+                    .iter()
+                    .any(|auto_exec| auto_exec.eq_ignore_ascii_case(&procedure.name))
+                {
+                    result.push((module.name.clone(), procedure.name));
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Returns the content hash covered by a digital signature of the given `kind`.
+    ///
+    /// This is the hash of the data specified in MS-OVBA sections 2.4.2.3 (Content Hash),
+    /// 2.4.2.4 (Agile Content Hash), and 2.4.2.6 (V3 Content Hash), respectively:
+    /// * [`SignatureKind::Legacy`] covers [`Project::content_normalized_data`].
+    /// * [`SignatureKind::Agile`] covers [`Project::content_normalized_data`], followed
+    ///   by [`Project::forms_normalized_data`].
+    /// * [`SignatureKind::V3`] covers [`Project::v3_content_normalized_data`], followed
+    ///   by [`Project::project_normalized_data`].
+    ///
+    /// To verify a signature, compare the result to the digest signed in the
+    /// signature's PKCS #7 data (see [`DigitalSignature::pkcs7`]), using the algorithm
+    /// recorded there. Verifying the signature itself is out of scope for this crate.
+    pub fn content_hash(&self, kind: SignatureKind, algorithm: HashAlgorithm) -> Result<Vec<u8>> {
+        let data = match kind {
+            SignatureKind::Legacy => self.content_normalized_data()?,
+            SignatureKind::Agile => {
+                let mut data = self.content_normalized_data()?;
+                data.extend(self.forms_normalized_data()?);
+                data
+            }
+            SignatureKind::V3 => {
+                let mut data = self.v3_content_normalized_data()?;
+                data.extend(self.project_normalized_data()?);
+                data
+            }
+        };
+
+        Ok(hash::digest(algorithm, &data))
+    }
+
+    /// Returns the ContentNormalizedData of the project.
+    ///
+    /// This is the data covered by the legacy digital signature (see
+    /// [`Project::digital_signature`]), as specified in MS-OVBA section 2.4.2.1. It is
+    /// made up of the project name and constants, the registered and project references,
+    /// and the source code of all modules, save for `Attribute` lines.
+    ///
+    /// The data is taken from the *dir* stream and module streams as stored, not from
+    /// [`Project::information`] and [`Project::references`]. It is therefore unaffected
+    /// by [`ProjectOptions::code_page_override`] and by changes to these fields.
+    ///
+    /// [`Project::content_hash`] computes the hash of this data, for comparison against
+    /// the signed digest.
+    pub fn content_normalized_data(&self) -> Result<Vec<u8>> {
+        let sources = self
+            .modules
+            .iter()
+            .map(|module| self.module_source_raw(&module.name))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(writer::write_content_normalized_data(
+            &self.dir_records()?,
+            &sources,
+        ))
+    }
+
+    /// Returns a stream's decompressed data.
+    ///
+    /// This function reads a stream referenced by `stream_path` and passes the data
+    /// starting at `offset` into the RLE decompressor.
+    ///
+    /// The primary use case for this function is to extract source code from VBA
+    /// [`Module`]s. The respective `offset` is reported by [`Module::text_offset`].
+    ///
+    /// This is a low-level function that is useful for very specific use cases only.
+    /// Client code that needs to read source code should use [`Project::module_source`]
+    /// or [`Project::module_source_raw`] instead.
+    ///
+    /// If `offset` doesn't point to a CompressedContainer, an [`Error::Decompressor`]
+    /// reporting an invalid SignatureByte at offset 0 is returned.
+    // TODO: Code example
+    pub fn decompress_stream_from<P>(&self, stream_path: P, offset: usize) -> Result<Vec<u8>>
+    where
+        P: AsRef<Path>,
+    {
+        let data = self.read_stream(stream_path)?;
+        let compressed = data.get(offset..).ok_or(Error::InvalidOffset {
+            offset,
+            len: data.len(),
+        })?;
+        self.decompress_container(compressed)
+    }
+
+    /// Returns the names of all designer storages.
+    ///
+    /// Designer modules (e.g. UserForms) store their layout in a storage alongside the
+    /// *VBA* storage, named after the respective module. Designer modules are listed in
+    /// the *PROJECT* stream; only those with an existing storage are returned.
+    pub fn designer_storages(&self) -> Result<Vec<String>> {
+        let properties = self.project_properties()?;
+        let container = self.container.borrow();
+        let storages = properties
+            .designer_modules
+            .into_iter()
+            .filter(|name| container.is_storage(self.project_path(&format!("/{}", name))))
+            .collect();
+
+        Ok(storages)
+    }
+
+    /// Returns a stream's data from the designer storage named `designer`.
+    ///
+    /// Designer storages commonly contain the streams `f` and `o`, holding the form's
+    /// control data, as well as the `\x03VBFrame` stream. The data is returned as is.
+    pub fn designer_stream(&self, designer: &str, stream: &str) -> Result<Vec<u8>> {
+        let storage_path = self.project_path(&format!("/{}", designer));
+        if !self.container.borrow().is_storage(&storage_path) {
+            return Err(Error::ModuleNotFound(designer.to_owned()));
+        }
+        self.read_stream(format!("{}/{}", storage_path, stream))
+    }
+
+    /// Returns the names of all streams in the designer storage named `designer`.
+    pub fn designer_streams(&self, designer: &str) -> Result<Vec<String>> {
+        let storage_path = self.project_path(&format!("/{}", designer));
+        let container = self.container.borrow();
+        if !container.is_storage(&storage_path) {
+            return Err(Error::ModuleNotFound(designer.to_owned()));
+        }
+        let streams = container
+            .read_storage(&storage_path)
+            .map_err(Error::Cfb)?
+            .filter(|entry| entry.is_stream())
+            .map(|entry| entry.name().to_owned())
+            .collect();
+
+        Ok(streams)
+    }
+
+    /// Compares the modules of this project against those of `other`.
+    ///
+    /// Modules are matched by name, see [`Module::name_eq`]. Source code is compared
+    /// after converting line endings (see [`ModuleSource::normalized_code`]), including
+    /// the `Attribute` lines. Metadata covers the module's description, Help topic,
+    /// type, and its read-only and private flags. The text offset isn't considered, as
+    /// it changes with the compiled code.
+    pub fn diff<G>(&self, other: &Project<G>) -> Result<ProjectDiff>
+    where
+        G: Read + Seek,
+    {
+        fn metadata(module: &Module) -> (&str, u32, ModuleType, bool, bool) {
+            (
+                &module.doc_string,
+                module.help_context,
+                module.module_type,
+                module.read_only,
+                module.private,
+            )
+        }
+
+        let mut diff = ProjectDiff::default();
+        for module in &self.modules {
+            let other_module = match other.module(&module.name) {
+                Some(other_module) => other_module,
+                None => {
+                    diff.removed.push(module.name.clone());
+                    continue;
+                }
+            };
+            let source = source::normalize_line_endings(&self.module_source(&module.name)?);
+            let other_source =
+                source::normalize_line_endings(&other.module_source(&other_module.name)?);
+            let module_diff = ModuleDiff {
+                name: module.name.clone(),
+                source_changed: source != other_source,
+                metadata_changed: metadata(module) != metadata(other_module),
+            };
+            if module_diff.source_changed || module_diff.metadata_changed {
+                diff.modified.push(module_diff);
+            }
+        }
+        diff.added = other
+            .modules
+            .iter()
+            .filter(|other_module| self.module(&other_module.name).is_none())
+            .map(|other_module| other_module.name.clone())
+            .collect();
+
+        Ok(diff)
+    }
+
+    /// Returns the project's digital signature, if it is signed.
+    ///
+    /// The signature streams are located in the storage holding the VBA project (see
+    /// [`Project::project_root`]). If multiple signature streams are present, the most
+    /// recent scheme is reported, in order [`SignatureKind::V3`],
+    /// [`SignatureKind::Agile`], and [`SignatureKind::Legacy`]. Verifying the signature is out of scope for this crate.
+    pub fn digital_signature(&self) -> Result<Option<DigitalSignature>> {
+        const SIGNATURE_STREAMS: &[(&str, SignatureKind)] = &[
+            ("/\x05DigitalSignatureV3", SignatureKind::V3),
+            ("/\x05DigitalSignatureAgile", SignatureKind::Agile),
+            ("/\x05DigitalSignature", SignatureKind::Legacy),
+        ];
+
+        for (path, kind) in SIGNATURE_STREAMS {
+            let path = self.project_path(path);
+            if self.stream_exists(&path) {
+                let data = self.read_stream(path)?;
+                return Ok(Some(DigitalSignature { kind: *kind, data }));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns all records of the *dir* stream, without interpreting them.
+    ///
+    /// Each record is reported as a tuple of its ID and its data. This provides access
+    /// to records not represented in the typed API, such as vendor extensions. Records
+    /// are split using the common layout of a 16-bit ID, followed by a 32-bit size,
+    /// followed by `size` bytes of data. The PROJECTVERSION Record (ID 0x0009), which
+    /// deviates from this layout, is reported with its 6 bytes of data.
+    pub fn dir_records(&self) -> Result<Vec<(u16, Vec<u8>)>> {
+        let data = self.decompress_stream_from(self.project_path(DIR_STREAM_PATH), 0)?;
+        let (_, records) =
+            parser::parse_dir_records(&data).map_err(|e| parser::to_error(e, &data))?;
+
+        Ok(records)
+    }
+
+    /// Returns the streams of embedded OLE objects.
+    ///
+    /// This isn't part of MS-OVBA. Office documents store embedded objects in storages
+    /// (e.g. below */ObjectPool*), holding a `\x01Ole` stream, and commonly a `CONTENTS`
+    /// stream with the object's data. Each such stream outside the *VBA* storage is
+    /// reported as a tuple of its path and its data, as is. Streams in the root storage
+    /// belong to the document itself, and are not reported.
+    pub fn embedded_objects(&self) -> Result<Vec<(String, Vec<u8>)>> {
+        const OBJECT_STREAMS: &[&str] = &["\x01Ole", "CONTENTS"];
+
+        let vba_storage = self.project_path("/VBA");
+        let mut objects = Vec::new();
+        for entry in self.list_entries()? {
+            let path = Path::new(&entry.path);
+            if entry.kind != EntryKind::Stream
+                || !OBJECT_STREAMS.contains(&entry.name.as_str())
+                || path.parent() == Some(Path::new("/"))
+                || path.starts_with(&vba_storage)
+            {
+                continue;
+            }
+            let data = self.read_stream(path)?;
+            objects.push((entry.path, data));
+        }
+
+        Ok(objects)
+    }
+
+    /// Returns the creation and modification times of an entry (storage or stream).
+    ///
+    /// The CFB stores a timestamp of 0 for times that are not set. MS-CFB requires this
+    /// for all times of streams, and for the root storage's creation time, although not
+    /// all implementations comply. Unset times are reported as `None`.
+    ///
+    /// Paths are interpreted as described for [`Project::read_stream`]. Returns
+    /// [`Error::MissingStream`] if there is no entry at `path`.
+    pub fn entry_times(&self, path: &str) -> Result<(Option<SystemTime>, Option<SystemTime>)> {
+        // January 1, 1601 (UTC), i.e. a CFB timestamp of 0.
+        let cfb_epoch = UNIX_EPOCH - Duration::from_secs(11_644_473_600);
+        let is_set = |time: SystemTime| if time == cfb_epoch { None } else { Some(time) };
+
+        let path = normalize_path(Path::new(path));
+        let container = self.container.borrow();
+        if !container.exists(&path) {
+            return Err(Error::MissingStream { path });
+        }
+        let entry = container.entry(&path).map_err(Error::Cfb)?;
+
+        Ok((is_set(entry.created()), is_set(entry.modified())))
+    }
+
+    /// Returns a module's source code in the format of a file exported from the VBA
+    /// editor.
+    ///
+    /// The VBA editor exports procedural modules as *.bas* files, holding the source
+    /// code as is. Class and document modules are exported as *.cls* files, and designer
+    /// modules as *.frm* files, see [`ModuleKind::file_extension`]. These start with a
+    /// header (`VERSION 1.0 CLASS` and `VERSION 5.00`, respectively), followed by a
+    /// `BEGIN`/`END` block, ahead of the source code.
+    ///
+    /// For designer modules, the block names the designer's class identifier, taken from
+    /// its storage (see [`Project::designer_storages`]), or the UserForm class identifier
+    /// if there is no storage. The designer's properties and controls are not
+    /// reconstructed; the VBA editor exports those to a separate *.frx* file.
+    pub fn export_module(&self, name: &str) -> Result<String> {
+        // The class identifier of Microsoft Forms 2.0 UserForms.
+        const USER_FORM_CLSID: &str = "C62A69F0-16DC-11CE-9E98-00AA00574A4F";
+
+        let module = self.find_module(name)?;
+        let header = match self.module_kind(name)? {
+            ModuleKind::Procedural => String::new(),
+            ModuleKind::Class | ModuleKind::Document => {
+                "VERSION 1.0 CLASS\r\nBEGIN\r\n  MultiUse = -1  'True\r\nEND\r\n".to_owned()
+            }
+            ModuleKind::Designer => {
+                let storage_path = self.project_path(&format!("/{}", module.name));
+                let clsid = match self.container.borrow().entry(&storage_path) {
+                    Ok(entry) if entry.is_storage() && !entry.clsid().is_nil() => {
+                        entry.clsid().to_string().to_uppercase()
+                    }
+                    _ => USER_FORM_CLSID.to_owned(),
+                };
+                format!(
+                    "VERSION 5.00\r\nBegin {{{}}} {} \r\nEnd\r\n",
+                    clsid, module.name
+                )
+            }
+        };
+
+        Ok(header + &self.module_source(name)?)
+    }
+
+    /// Returns the FormsNormalizedData of the project, as specified in MS-OVBA section
+    /// 2.4.2.2.
+    ///
+    /// This is made up of the streams in all designer storages (see
+    /// [`Project::designer_storages`]), including nested storages. Each stream's data is
+    /// padded with `0x00` bytes to a multiple of 1023 bytes.
+    pub fn forms_normalized_data(&self) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        for designer in self.designer_storages()? {
+            self.normalize_storage(&self.project_path(&format!("/{}", designer)), &mut data)?;
+        }
+
+        Ok(data)
+    }
+
+    /// Returns the Help file and Help topic identifier associated with a module.
+    ///
+    /// Returns `None` if the project doesn't have a Help file.
+    pub fn help_topic(&self, name: &str) -> Result<Option<(&str, u32)>> {
+        let module = self.find_module(name)?;
+        let help_file = self.information.help_file();
+        if help_file.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some((help_file, module.help_context)))
+    }
+
+    /// Returns whether the VBA project is locked for viewing.
+    ///
+    /// A locked project requires a password to view its source code in the VBA editor.
+    /// This is recorded in the encrypted `GC=` property of the *PROJECT* stream, which
+    /// this function decrypts (MS-OVBA 2.4.3). Projects without this property are
+    /// reported as not locked.
+    ///
+    /// Protection is enforced by the VBA editor only. The source code is stored
+    /// unencrypted either way, and can be read through [`Project::module_source`].
+    pub fn is_locked(&self) -> Result<bool> {
+        // ProjectVisibilityState: 0x00 if not visible, 0xFF if visible
+        const NOT_VISIBLE: &[u8] = &[0x00];
+
+        let visibility_state = self.protection()?.visibility_state;
+
+        Ok(visibility_state.as_deref() == Some(NOT_VISIBLE))
+    }
+
+    /// Returns whether the project shows signs of "VBA stomping".
+    ///
+    /// VBA stomping refers to tampering with a module's source code, leaving the compiled
+    /// p-code (which is what actually executes) intact. This function flags projects
+    /// where
+    /// * a module's source code is empty (save for `Attribute` lines), while its stream
+    ///   holds a PerformanceCache, or
+    /// * the *_VBA_PROJECT* stream's version disagrees with the *dir* stream: A version
+    ///   of 0xFFFF declares that there is no PerformanceCache, yet the MODULEOFFSET
+    ///   Record of a module reports one.
+    ///
+    /// Document modules (e.g. *ThisWorkbook*) are compiled even if they hold no code.
+    /// Their PerformanceCache is only considered if it exceeds 2048 bytes. SRP streams
+    /// cannot be attributed to individual modules, and are not considered.
+    ///
+    /// Modules are checked independently: A module whose source code cannot be read
+    /// (e.g. a destroyed CompressedContainer) counts as empty, and a module that cannot
+    /// be classified through the *PROJECT* stream isn't treated as a document module. A
+    /// malformed *_VBA_PROJECT* stream is ignored.
+    ///
+    /// This is a heuristic, not proof. A `true` result calls for further analysis of the
+    /// p-code, a `false` result doesn't guarantee that source code and p-code agree.
+    pub fn is_potentially_stomped(&self) -> Result<bool> {
+        // The version written by tools that don't produce a PerformanceCache
+        const NO_PERFORMANCE_CACHE: u16 = 0xffff;
+        const MAX_EMPTY_DOCUMENT_CACHE_LEN: usize = 2048;
+
+        let has_performance_cache = self.modules.iter().any(|module| module.text_offset > 0);
+        if has_performance_cache
+            && matches!(self.vba_version(), Ok(version) if version.version == NO_PERFORMANCE_CACHE)
+        {
+            return Ok(true);
+        }
+
+        for module in &self.modules {
+            let min_cache_len = match self.module_kind(&module.name) {
+                Ok(ModuleKind::Document) => MAX_EMPTY_DOCUMENT_CACHE_LEN + 1,
+                _ => 1,
+            };
+            if module.text_offset >= min_cache_len
+                && self.module_is_empty(&module.name).unwrap_or(true)
+            {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Returns all modules paired with their source code.
+    ///
+    /// This is a convenience function that calls [`Project::module_source`] for each
+    /// module, in order of appearance in the *dir* stream. The first failure aborts the
+    /// iteration and is returned.
+    pub fn iter_modules(&self) -> Result<Vec<(&Module, String)>> {
+        self.modules
+            .iter()
+            .map(|module| Ok((module, self.module_source(&module.name)?)))
+            .collect()
+    }
+
+    // TODO: This should probably live someplace else. It exposes information internal to
+    //       the CFB implementation, that's not *immediately* useful or related to this
+    //       library's primary responsibility.
+
+    /// Returns a list of entries (storages and streams) in the raw binary data. Each
+    /// entry is represented as a tuple of two `String`s, where the first element
+    /// contains the entry's name and the second element the entry's path inside the
+    /// CFB.
+    ///
+    /// The raw binary data is encoded as a [Compound File Binary][MS-CFB]
+    ///
+    /// [MS-CFB]: https://docs.microsoft.com/en-us/openspecs/windows_protocols/ms-cfb/53989ce4-7b05-4f8d-829b-d08d6148375b
+    pub fn list(&self) -> Result<Vec<(String, String)>> {
+        self.list_matching("")
+    }
+
+    /// Returns a list of entries (storages and streams) in the raw binary data.
+    ///
+    /// This is the same as [`Project::list`], with each entry's kind reported in
+    /// addition to its name and path. The root storage is reported as a storage at
+    /// path `/`.
+    pub fn list_entries(&self) -> Result<Vec<Entry>> {
+        let entries = self
+            .container
+            .borrow()
+            .walk_storage("/")
+            .map_err(Error::Cfb)?
+            .map(|entry| Entry {
+                name: entry.name().to_owned(),
+                path: entry.path().to_str().unwrap_or_default().to_owned(),
+                kind: if entry.is_stream() {
+                    EntryKind::Stream
+                } else {
+                    EntryKind::Storage
+                },
+            })
+            .collect();
+
+        Ok(entries)
+    }
+
+    /// Returns a list of entries whose path starts with `prefix`.
+    ///
+    /// This is the same as [`Project::list`], restricted to matching entries. Paths are
+    /// compared as reported, e.g. `/VBA/__SRP_` selects all SRP streams.
+    pub fn list_matching(&self, prefix: &str) -> Result<Vec<(String, String)>> {
+        let mut result = Vec::new();
+        for entry in self
+            .container
+            .borrow()
+            .walk_storage("/")
+            .map_err(Error::Cfb)?
+        {
+            let path = entry.path().to_str().unwrap_or_default();
+            if path.starts_with(prefix) {
+                result.push((entry.name().to_owned(), path.to_owned()));
+            }
+        }
+        Ok(result)
+    }
+
+    /// Returns a description of the project and all of its modules, see [`Manifest`].
+    ///
+    /// Details that cannot be determined, such as those taken from a missing *PROJECT*
+    /// stream or a malformed module stream, are reported as `None`, as is common with
+    /// stripped or tampered files.
+    pub fn manifest(&self) -> Result<Manifest> {
+        let modules = self
+            .modules
+            .iter()
+            .map(|module| ModuleManifest {
+                name: module.name.clone(),
+                kind: self.module_kind(&module.name).ok(),
+                stream_path: self.module_stream_path(module),
+                source_hash: self
+                    .module_source_hash(&module.name, HashAlgorithm::Sha256)
+                    .ok(),
+                stream_size: self.module_stream_size(&module.name).ok(),
+            })
+            .collect();
+
+        Ok(Manifest {
+            information: self.information.clone(),
+            references: self.references.clone(),
+            modules,
+            signature: self.digital_signature()?.map(|signature| signature.kind),
+            is_locked: self.is_locked().ok(),
+        })
+    }
+
+    /// Returns the module called `name`, if any.
+    ///
+    /// Names are compared using [`Module::name_eq`].
+    ///
+    /// All functions that take a module name, such as [`Project::module_source`], look up
+    /// modules this way.
+    pub fn module(&self, name: &str) -> Option<&Module> {
+        self.modules.iter().find(|module| module.name_eq(name))
+    }
+
+    /// Returns the compressed source code of a module.
+    ///
+    /// The result is the CompressedContainer at [`Module::text_offset`], as stored in
+    /// the module's stream, i.e. the input to [`Project::module_source_raw`]. The
+    /// container is decompressed to validate it, honoring the [`ProjectOptions`]. With
+    /// lenient parsing, the result ends at the last complete chunk, excluding any
+    /// trailing data.
+    pub fn module_compressed_source(&self, name: &str) -> Result<Vec<u8>> {
+        let module = self.find_module(name)?;
+        let mut compressed = self.read_compressed_source(module)?;
+        let len = if self.options.parse.lenient {
+            decompress_partial(&compressed)?.1
+        } else {
+            self.decompress_container(&compressed)?;
+            compressed.len()
+        };
+        compressed.truncate(len);
+
+        Ok(compressed)
+    }
+
+    /// Returns whether a module's source code is empty, save for blank lines and
+    /// `Attribute` lines.
+    ///
+    /// The source code is decompressed chunk by chunk, stopping at the first line of
+    /// code. This avoids decompressing (and decoding) all of a large module, but also
+    /// means that malformed data past that line goes undetected. The limit set through
+    /// [`ProjectOptions::max_decompressed`] applies. Lines exceeding 64 KiB are treated
+    /// as code.
+    pub fn module_is_empty(&self, name: &str) -> Result<bool> {
+        // The VBA editor limits lines to 1023 characters. Longer lines are bound to be
+        // code, and scanning them for a line break is pointless.
+        const MAX_LINE_LEN: usize = 0x1_0000;
+
+        let module = self.find_module(name)?;
+        let compressed = self.read_compressed_source(module)?;
+        let code_page = self.information.code_page;
+        let max_len = self.options.max_decompressed.unwrap_or(usize::MAX);
+        let is_empty = |data: &[u8]| {
+            cp_to_string(data, code_page)
+                .lines()
+                .all(source::is_blank_or_attribute)
+        };
+
+        let to_error = |e| parser::to_decompressor_error(e, &compressed);
+        let (mut i, _) = parser::container_signature(&compressed).map_err(to_error)?;
+        // Lines can span chunks, so the last (incomplete) line is kept for the next chunk.
+        let mut pending = Vec::new();
+        let mut decompressed = 0_usize;
+        while !i.is_empty() {
+            let (remainder, data) = match parser::chunk_parser(i) {
+                Ok(chunk) => chunk,
+                Err(_) if self.options.parse.lenient => break,
+                Err(e) => return Err(to_error(e)),
+            };
+            if data.len() > max_len - decompressed {
+                return Err(Error::Decompressor {
+                    offset: compressed.len() - i.len(),
+                    reason: "Decompressed data exceeds limit",
+                });
+            }
+            decompressed += data.len();
+
+            // Only the new data can contain the end of the pending line.
+            let searched = pending.len();
+            pending.extend(data);
+            if let Some(end) = pending[searched..].iter().rposition(|&b| b == b'\n') {
+                let end = searched + end;
+                if !is_empty(&pending[..end]) {
+                    return Ok(false);
+                }
+                pending.drain(..=end);
+            }
+            if pending.len() > MAX_LINE_LEN {
+                return Ok(false);
+            }
+            i = remainder;
+        }
+
+        Ok(is_empty(&pending))
+    }
+
+    /// Returns a module's kind.
+    ///
+    /// Procedural modules are identified from the *dir* stream alone. For all other
+    /// modules, this function consults the *PROJECT* stream to tell document, class,
+    /// and designer modules apart.
+    pub fn module_kind(&self, name: &str) -> Result<ModuleKind> {
+        let module = self.find_module(name)?;
+
+        if let ModuleType::Procedural = module.module_type {
+            return Ok(ModuleKind::Procedural);
+        }
+
+        let properties = self.project_properties()?;
+        let contains = |names: &[String]| names.iter().any(|n| module.name_eq(n));
+        if contains(&properties.document_modules) {
+            Ok(ModuleKind::Document)
+        } else if contains(&properties.designer_modules) {
+            Ok(ModuleKind::Designer)
+        } else if contains(&properties.class_modules) {
+            Ok(ModuleKind::Class)
+        } else {
+            // Every module MUST have a corresponding entry in the *PROJECT* stream.
+            Err(Error::ModuleNotFound(name.to_owned()))
+        }
+    }
+
+    /// Returns the module name map from the *PROJECTwm* stream.
+    ///
+    /// Each entry maps a module's MBCS name to its Unicode equivalent. This is useful to
+    /// resolve module names that cannot be represented faithfully in the project's code
+    /// page.
+    pub fn module_name_map(&self) -> Result<Vec<(String, String)>> {
+        const PROJECTWM_STREAM_PATH: &str = "/PROJECTwm";
+
+        let data = self.read_stream(self.project_path(PROJECTWM_STREAM_PATH))?;
+        let (_, map) = parser::parse_module_name_map(&data, self.information.code_page)
+            .map_err(|e| parser::to_error(e, &data))?;
+
+        Ok(map)
+    }
+
+    /// Returns the raw *dir* stream records of a module, without interpreting them.
+    ///
+    /// This is the module-scoped equivalent of [`Project::dir_records`]. The result
+    /// spans the module's records from MODULENAME (ID 0x0019) through the Terminator
+    /// (ID 0x002B), and can be used to verify the values reported by [`Module`].
+    pub fn module_records(&self, name: &str) -> Result<Vec<(u16, Vec<u8>)>> {
+        const PROJECTCOOKIE_ID: u16 = 0x0013;
+        const MODULE_TERMINATOR_ID: u16 = 0x002b;
+
+        let module = self.find_module(name)?;
+        let index = self
+            .modules
+            .iter()
+            .position(|m| std::ptr::eq(m, module))
+            .unwrap_or_default();
+
+        let records = self.dir_records()?;
+        // Module records follow the PROJECTCOOKIE Record.
+        let start = records
+            .iter()
+            .position(|(id, _)| *id == PROJECTCOOKIE_ID)
+            .map_or(records.len(), |position| position + 1);
+        let module = records[start..]
+            .split_inclusive(|(id, _)| *id == MODULE_TERMINATOR_ID)
+            .nth(index)
+            .ok_or_else(|| Error::ModuleNotFound(name.to_owned()))?;
+
+        Ok(module.to_vec())
+    }
+
+    /// Returns a module's source code.
+    ///
+    /// Similar to [`Project::module_source_raw`] this function returns the source code
+    /// of a project's module. After the raw source code has been decoded it is then
+    /// converted to a `String` using the project's code page.
+    ///
+    /// The decompressed source code is converted as a whole, so that characters in
+    /// double-byte code pages (e.g. Shift-JIS) decode correctly, even when they span
+    /// CompressedChunk boundaries.
+    pub fn module_source(&self, name: &str) -> Result<String> {
+        let source_raw = self.module_source_raw(name)?;
+        let source = cp_to_string(&source_raw, self.information.code_page);
+
+        Ok(source)
+    }
+
+    /// Returns the digest of a module's source code, using `algorithm`.
+    ///
+    /// The digest is computed over the UTF-8 encoded [`ModuleSource::normalized_code`],
+    /// i.e. excluding the leading `Attribute` lines, and independent of line endings.
+    /// The same code thus hashes identically across documents, e.g. to find shared macros
+    /// in a corpus.
+    pub fn module_source_hash(&self, name: &str, algorithm: HashAlgorithm) -> Result<Vec<u8>> {
+        let code = self.module_source_parts(name)?.normalized_code();
+
+        Ok(hash::digest(algorithm, code.as_bytes()))
+    }
+
+    /// Returns a module's source code, with the leading `Attribute` lines separated from
+    /// the code body.
+    ///
+    /// See [`Project::module_source`] and [`ModuleSource`].
+    pub fn module_source_parts(&self, name: &str) -> Result<ModuleSource> {
+        let source = self.module_source(name)?;
+
+        Ok(ModuleSource::from(source.as_str()))
+    }
+
+    /// Returns the raw source code from a module.
+    ///
+    /// The result contains a module's source code as is. No character encoding conversion
+    /// is done. The data is encoded using the project's code page available through
+    /// [`Information::code_page`].
+    ///
+    /// The source code is read from the module's stream, starting at
+    /// [`Module::text_offset`], i.e. past the PerformanceCache. If no CompressedContainer
+    /// starts at this offset, [`Error::InvalidTextOffset`] is returned.
+    pub fn module_source_raw(&self, name: &str) -> Result<Vec<u8>> {
+        let module = self.find_module(name)?;
+        let compressed = self.read_compressed_source(module)?;
+        let src_code = self.decompress_container(&compressed)?;
+
+        Ok(src_code)
+    }
+
+    /// Returns the size of a module's stream in bytes, without reading its data.
+    ///
+    /// The size includes the PerformanceCache (see [`Project::performance_cache_len`])
+    /// as well as the compressed source code.
+    pub fn module_stream_size(&self, name: &str) -> Result<u64> {
+        let module = self.find_module(name)?;
+        let path = self.module_stream_path(module);
+        let path = self.require_stream(Path::new(&path))?;
+        let size = self
+            .container
+            .borrow()
+            .entry(path)
+            .map_err(Error::Cfb)?
+            .len();
+
+        Ok(size)
+    }
+
+    /// Opens a stream for incremental reading.
+    ///
+    /// Unlike [`Project::read_stream`], this doesn't read the entire stream into memory.
+    /// This is useful to inspect a stream's header before deciding whether to read the
+    /// remainder. Paths are interpreted as described for [`Project::read_stream`].
+    pub fn open_stream_reader(&mut self, stream_path: &str) -> Result<impl Read + Seek + '_> {
+        let path = self.require_stream(Path::new(stream_path))?;
+        self.container
+            .get_mut()
+            .open_stream(path)
+            .map_err(Error::Cfb)
+    }
+
+    /// Returns the length of the PerformanceCache that precedes a module's compressed
+    /// source code.
+    ///
+    /// This is the module's [`Module::text_offset`]. A length of 0 indicates that the
+    /// module stream doesn't contain a PerformanceCache.
+    pub fn performance_cache_len(&self, name: &str) -> Result<usize> {
+        let module = self.find_module(name)?;
+
+        Ok(module.text_offset)
+    }
+
+    /// Returns the ProjectNormalizedData of the project, as specified in MS-OVBA section
+    /// 2.4.2.6.
+    ///
+    /// This is made up of the *PROJECT* stream's properties, save for those that change
+    /// without a change to the project's content (`ID`, `Document`, and the encrypted
+    /// `CMG`, `DPB`, and `GC`), and its `[Host Extender Info]` section. The
+    /// normalized data of each designer storage (see [`Project::forms_normalized_data`])
+    /// precedes the respective `BaseClass` property.
+    pub fn project_normalized_data(&self) -> Result<Vec<u8>> {
+        const PROJECT_STREAM_PATH: &str = "/PROJECT";
+
+        let data = self.read_stream(self.project_path(PROJECT_STREAM_PATH))?;
+        writer::write_project_normalized_data(&data, |name| {
+            let path = self.project_path(&format!(
+                "/{}",
+                cp_to_string(name, self.information.code_page)
+            ));
+            let mut data = Vec::new();
+            if self.container.borrow().is_storage(&path) {
+                self.normalize_storage(&path, &mut data)?;
+            }
+            Ok(data)
+        })
+    }
+
+    /// Returns the project properties stored in the *PROJECT* stream.
+    pub fn project_properties(&self) -> Result<ProjectProperties> {
+        const PROJECT_STREAM_PATH: &str = "/PROJECT";
+
+        let data = self.read_stream(self.project_path(PROJECT_STREAM_PATH))?;
+        let text = cp_to_string(&data, self.information.code_page);
+
+        Ok(parser::parse_project_properties(&text))
+    }
+
+    /// Returns the path of the storage holding the VBA project.
+    ///
+    /// This is `/` for raw binary VBA projects (e.g. *vbaProject.bin*). Office documents
+    /// in the binary file formats store the VBA project in a storage, such as */Macros*
+    /// (Word) or */_VBA_PROJECT_CUR* (Excel). Use this to build paths for
+    /// [`Project::read_stream`] that refer to the project's streams.
+    pub fn project_root(&self) -> &str {
+        if self.root.is_empty() {
+            "/"
+        } else {
+            &self.root
+        }
+    }
+
+    /// Returns the decrypted protection properties of the *PROJECT* stream.
+    ///
+    /// The `CMG=`, `DPB=`, and `GC=` properties are decrypted using the Data Encryption
+    /// algorithm (MS-OVBA 2.4.3). The results are returned as is, for use by external
+    /// tools; see [`Protection`] for their structure. This function doesn't attempt to
+    /// recover passwords.
+    pub fn protection(&self) -> Result<Protection> {
+        let decrypt = |value: Option<String>| match value {
+            Some(value) => parser::decrypt_data(&value).map(Some),
+            None => Ok(None),
+        };
+
+        let properties = self.project_properties()?;
+        Ok(Protection {
+            protection_state: decrypt(properties.protection_state)?,
+            password: decrypt(properties.password)?,
+            visibility_state: decrypt(properties.visibility_state)?,
+        })
+    }
+
+    /// Returns a stream's contents.
+    ///
+    /// This is a low-level function operating on the CFB data. The CFB is the storage
+    /// container of the raw binary VBA project.
+    ///
+    /// Paths are absolute, with storage and stream names separated by `/`, e.g.
+    /// `/VBA/dir`. Backslashes are accepted as separators as well, and a missing
+    /// leading `/` is implied, so `VBA/dir` and `VBA\dir` refer to the same stream.
+    /// Paths start at the CFB's root, which isn't necessarily the storage holding the
+    /// VBA project (e.g. */Macros* in Word documents).
+    ///
+    /// Returns [`Error::MissingStream`] if there is no stream at `stream_path`, and
+    /// [`Error::Io`] if reading the stream's data fails.
+    pub fn read_stream<P>(&self, stream_path: P) -> Result<Vec<u8>>
+    where
+        P: AsRef<Path>,
+    {
+        let path = self.require_stream(stream_path.as_ref())?;
+        let mut buffer = Vec::new();
+        self.container
+            .borrow_mut()
+            .open_stream(path)
+            .map_err(Error::Cfb)?
+            .read_to_end(&mut buffer)?;
+
+        Ok(buffer)
+    }
+
+    /// Writes the project to `writer`, as a CFB.
+    ///
+    /// The output reflects all modifications made through [`Project::set_module_source`].
+    /// Storages and streams are copied along with their class identifiers and state bits.
+    /// Creation and modification times are not preserved.
+    pub fn save<W>(&self, mut writer: W) -> Result<()>
+    where
+        W: Write,
+    {
+        let (version, entries) = {
+            let container = self.container.borrow();
+            let entries = container
+                .walk_storage("/")
+                .map_err(Error::Cfb)?
+                .collect::<Vec<_>>();
+            (container.version(), entries)
+        };
+
+        let mut target = CompoundFile::create_with_version(version, Cursor::new(Vec::new()))
+            .map_err(Error::Cfb)?;
+        for entry in &entries {
+            let path = entry.path();
+            if entry.is_stream() {
+                let data = self.read_stream(path)?;
+                target
+                    .create_stream(path)
+                    .map_err(Error::Cfb)?
+                    .write_all(&data)
+                    .map_err(Error::Cfb)?;
+            } else {
+                if !entry.is_root() {
+                    target.create_storage(path).map_err(Error::Cfb)?;
+                }
+                target
+                    .set_storage_clsid(path, *entry.clsid())
+                    .map_err(Error::Cfb)?;
+            }
+            target
+                .set_state_bits(path, entry.state_bits())
+                .map_err(Error::Cfb)?;
+        }
+
+        target.flush().map_err(Error::Cfb)?;
+        writer.write_all(&target.into_inner().into_inner())?;
+
+        Ok(())
+    }
+
+    /// Returns the names of all `__SRP_*` streams in the *VBA* storage.
+    ///
+    /// SRP streams hold cached compilation artifacts. Their format is undocumented, and
+    /// this crate doesn't support parsing them. Their mere presence can be an indicator
+    /// of source code and p-code having diverged.
+    pub fn srp_streams(&self) -> Result<Vec<String>> {
+        const VBA_STORAGE_PATH: &str = "/VBA";
+
+        let streams = self
+            .container
+            .borrow()
+            .read_storage(self.project_path(VBA_STORAGE_PATH))
+            .map_err(Error::Cfb)?
+            .filter(|entry| entry.is_stream() && entry.name().starts_with("__SRP_"))
+            .map(|entry| entry.name().to_owned())
+            .collect();
+
+        Ok(streams)
+    }
+
+    /// Returns whether a stream exists at `stream_path`.
+    ///
+    /// This is useful to check for optional streams, such as *PROJECTwm* or a digital
+    /// signature, before reading them. Storages are not reported as streams. Paths are
+    /// interpreted as described for [`Project::read_stream`].
+    pub fn stream_exists(&self, stream_path: &str) -> bool {
+        self.container
+            .borrow()
+            .is_stream(normalize_path(Path::new(stream_path)))
+    }
+
+    /// Returns an overview of the project, see [`ProjectSummary`].
+    ///
+    /// Information from the *PROJECT* and *_VBA_PROJECT* streams is reported as `None`
+    /// if the respective stream is missing or malformed, as is common with stripped or
+    /// tampered files.
+    pub fn summary(&self) -> Result<ProjectSummary> {
+        Ok(ProjectSummary {
+            name: self.information.name().to_owned(),
+            sys_kind: self.information.sys_kind,
+            code_page: self.information.code_page,
+            module_count: self.modules.len(),
+            reference_count: self.references.len(),
+            has_signature: self.digital_signature()?.is_some(),
+            is_locked: self.is_locked().ok(),
+            vba_version: self.vba_version().ok().map(|version| version.version),
+        })
+    }
+
+    /// Returns the hierarchy of storages and streams in the raw binary data, starting at
+    /// the root storage.
+    ///
+    /// This holds the same entries as [`Project::list_entries`], nested according to
+    /// their paths. Children are reported in the same order as by
+    /// [`Project::list_entries`].
+    pub fn tree(&self) -> Result<StorageNode> {
+        fn parent(path: &str) -> &str {
+            match path.rfind('/') {
+                Some(0) | None => "/",
+                Some(index) => &path[..index],
+            }
+        }
+
+        fn build(entry: &Entry, entries: &[Entry]) -> StorageNode {
+            let mut node = StorageNode {
+                name: entry.name.clone(),
+                path: entry.path.clone(),
+                storages: Vec::new(),
+                streams: Vec::new(),
+            };
+            let children = entries
+                .iter()
+                .filter(|child| child.path != "/" && parent(&child.path) == entry.path);
+            for child in children {
+                match child.kind {
+                    EntryKind::Storage => node.storages.push(build(child, entries)),
+                    EntryKind::Stream => node.streams.push(child.clone()),
+                }
+            }
+            node
+        }
+
+        let root = Entry {
+            name: self.container.borrow().root_entry().name().to_owned(),
+            path: "/".to_owned(),
+            kind: EntryKind::Storage,
+        };
+        let entries = self.list_entries()?;
+
+        Ok(build(&root, &entries))
+    }
+
+    /// Returns the V3ContentNormalizedData of the project, as specified in MS-OVBA
+    /// section 2.4.2.5.
+    ///
+    /// This is made up of the *dir* stream's records, save for values that change
+    /// without a change to the project's content (such as offsets and cookies), and the
+    /// source code of all modules. Unlike [`Project::content_normalized_data`], this
+    /// covers all kinds of references, and `Attribute` lines other than `VB_Name` and
+    /// those with default values. As with [`Project::content_normalized_data`], the
+    /// records are used as stored, including their Unicode variants.
+    pub fn v3_content_normalized_data(&self) -> Result<Vec<u8>> {
+        let sources = self
+            .modules
+            .iter()
+            .map(|module| self.module_source_raw(&module.name))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(writer::write_v3_content_normalized_data(
+            &self.dir_records()?,
+            &sources,
+        ))
+    }
+
+    /// Returns the header information of the *_VBA_PROJECT* stream.
+    pub fn vba_version(&self) -> Result<VbaVersion> {
+        const VBA_PROJECT_STREAM_PATH: &str = "/VBA/_VBA_PROJECT";
+
+        let data = self.read_stream(self.project_path(VBA_PROJECT_STREAM_PATH))?;
+        let (_, version) =
+            parser::parse_vba_version(&data).map_err(|e| parser::to_error(e, &data))?;
+
+        Ok(version)
+    }
+
+    /// Decompresses a CompressedContainer, honoring the [`ProjectOptions`].
+    fn decompress_container(&self, compressed: &[u8]) -> Result<Vec<u8>> {
+        self.options.decompress(compressed)
+    }
+
+    fn find_module(&self, name: &str) -> Result<&Module> {
+        self.module(name)
+            .ok_or_else(|| Error::ModuleNotFound(name.to_owned()))
+    }
+
+    fn module_stream_path(&self, module: &Module) -> String {
+        // The MBCS stream name is authoritative. If it fails to resolve (e.g. because
+        // the project's code page cannot represent it faithfully), retry with the
+        // Unicode stream name.
+        let path = self.project_path(&format!("/VBA/{}", &module.stream_name));
+        if self.stream_exists(&path) {
+            path
+        } else {
+            self.project_path(&format!("/VBA/{}", &module.stream_name_unicode))
+        }
+    }
+
+    /// Appends the streams of the storage at `path` to `data`, as specified for the
+    /// FormsNormalizedData. Nested storages are processed recursively.
+    fn normalize_storage(&self, path: &str, data: &mut Vec<u8>) -> Result<()> {
+        const BLOCK_SIZE: usize = 1023;
+
+        let entries = self
+            .container
+            .borrow()
+            .read_storage(path)
+            .map_err(Error::Cfb)?
+            .map(|entry| (entry.path().to_owned(), entry.is_stream()))
+            .collect::<Vec<_>>();
+        for (path, is_stream) in entries {
+            if !is_stream {
+                self.normalize_storage(path.to_str().unwrap_or_default(), data)?;
+                continue;
+            }
+            let stream = self.read_stream(&path)?;
+            data.extend_from_slice(&stream);
+            let remainder = stream.len() % BLOCK_SIZE;
+            if remainder != 0 {
+                data.resize(data.len() + BLOCK_SIZE - remainder, 0x00);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads a module's stream, returning the CompressedContainer at
+    /// [`Module::text_offset`] and all data following it.
+    fn read_compressed_source(&self, module: &Module) -> Result<Vec<u8>> {
+        const COMPRESSED_CONTAINER_SIGNATURE: u8 = 0x01;
+
+        let path = self.module_stream_path(module);
+        let offset = module.text_offset;
+        let mut data = self.read_stream(path)?;
+        // The source code follows the PerformanceCache. A wrong offset would otherwise
+        // surface as an obscure decompressor error, or garbage in lenient mode.
+        if data.get(offset) != Some(&COMPRESSED_CONTAINER_SIGNATURE) {
+            return Err(Error::InvalidTextOffset {
+                module: module.name.clone(),
+                offset,
+            });
+        }
+
+        Ok(data.split_off(offset))
+    }
+
+    /// Returns the normalized `stream_path`, or [`Error::MissingStream`] if there is no
+    /// stream at this path.
+    fn require_stream(&self, stream_path: &Path) -> Result<String> {
+        let path = normalize_path(stream_path);
+        if self.container.borrow().is_stream(&path) {
+            Ok(path)
+        } else {
+            Err(Error::MissingStream { path })
+        }
+    }
+
+    /// Returns the path of an entry relative to the project root.
+    fn project_path(&self, path: &str) -> String {
+        format!("{}{}", self.root, path)
+    }
+}
+
+impl<F> Project<F>
+where
+    F: Read + Write + Seek,
+{
+    /// Adds a module to the project.
+    ///
+    /// This creates the module stream holding the compressed `source`, appends a
+    /// [`Module`] to [`Project::modules`], and adds the respective entry to the *PROJECT*
+    /// stream. Only procedural and class modules can be added: Modules of type
+    /// [`ModuleType::DocClsDesigner`] are added as class modules, since document and
+    /// designer modules depend on the host document and a designer storage,
+    /// respectively.
+    ///
+    /// `source` is stored as is. Applications commonly expect it to start with an
+    /// `Attribute VB_Name = "..."` line.
+    ///
+    /// Since the compiled code no longer matches the set of modules, it is discarded
+    /// through [`Project::discard_performance_cache`], which also updates the *dir*
+    /// stream.
+    ///
+    /// Returns [`Error::InvalidModuleName`] if `name` isn't a valid VBA identifier of at
+    /// most 31 characters, as required for module names. Returns
+    /// [`Error::DuplicateModule`] if a module or stream named `name` already exists.
+    pub fn add_module(&mut self, name: &str, source: &str, module_type: ModuleType) -> Result<()> {
+        const MAX_NAME_LEN: usize = 31;
+
+        // A VBA identifier starts with a letter, followed by letters, digits, and
+        // underscores.
+        let mut chars = name.chars();
+        let is_identifier = matches!(chars.next(), Some(c) if c.is_alphabetic())
+            && chars.all(|c| c.is_alphanumeric() || c == '_');
+        if !is_identifier || name.chars().count() > MAX_NAME_LEN {
+            return Err(Error::InvalidModuleName(name.to_owned()));
+        }
+        if self.find_module(name).is_ok() {
+            return Err(Error::DuplicateModule(name.to_owned()));
+        }
+        let module = Module {
+            name: name.to_owned(),
+            name_unicode: name.to_owned(),
+            stream_name: name.to_owned(),
+            stream_name_unicode: name.to_owned(),
+            doc_string: String::new(),
+            doc_string_unicode: String::new(),
+            text_offset: 0,
+            help_context: 0,
+            module_type,
+            read_only: false,
+            private: false,
+        };
+        let path = self.module_stream_path(&module);
+        if self.container.borrow().exists(&path) {
+            return Err(Error::DuplicateModule(name.to_owned()));
+        }
+
+        let code_page = self.information.code_page;
+        let source = string_to_cp(source, code_page).ok_or(Error::Encoding { code_page })?;
+        self.write_stream(&path, &compress(&source))?;
+
+        let key = match module.module_type {
+            ModuleType::Procedural => "Module",
+            ModuleType::DocClsDesigner => "Class",
+        };
+        self.update_project_stream(|lines| {
+            // Module entries follow the `ID=` line, ahead of all other properties.
+            let end = lines
+                .iter()
+                .position(|line| line.starts_with('['))
+                .unwrap_or(lines.len());
+            let index = lines[..end]
+                .iter()
+                .rposition(|line| {
+                    let key = line.split('=').next().unwrap_or_default();
+                    MODULE_KEYS.contains(&key) || key == "ID" || key == "Package"
+                })
+                .map_or(0, |index| index + 1);
+            lines.insert(index, format!("{}={}", key, name));
+        })?;
+
+        self.modules.push(module);
+        self.discard_performance_cache()
+    }
+
+    /// Discards the compiled code of all modules.
+    ///
+    /// This removes the PerformanceCache from each module stream, setting
+    /// [`Module::text_offset`] to 0, and writes a *_VBA_PROJECT* stream with a Version
+    /// of 0xFFFF and no PerformanceCache, as MS-OVBA requires when no PerformanceCache
+    /// is written. Office applications then compile the project from its source code.
+    /// The *dir* stream is updated through [`Project::update_dir_stream`].
+    ///
+    /// Returns [`Error::InvalidOffset`] if a module's stream is shorter than its
+    /// [`Module::text_offset`].
+    pub fn discard_performance_cache(&mut self) -> Result<()> {
+        const VBA_PROJECT_STREAM_PATH: &str = "/VBA/_VBA_PROJECT";
+        // Reserved1 (0x61CC), Version (0xFFFF), Reserved2, and Reserved3
+        const VBA_PROJECT_STREAM: &[u8] = b"\xCC\x61\xFF\xFF\x00\x00\x00";
+
+        for index in 0..self.modules.len() {
+            let module = &self.modules[index];
+            let offset = module.text_offset;
+            if offset == 0 {
+                continue;
+            }
+            let path = self.module_stream_path(module);
+            let data = self.read_stream(&path)?;
+            let compressed = data.get(offset..).ok_or(Error::InvalidOffset {
+                offset,
+                len: data.len(),
+            })?;
+            self.write_stream(&path, compressed)?;
+            self.modules[index].text_offset = 0;
+        }
+
+        let path = self.project_path(VBA_PROJECT_STREAM_PATH);
+        self.write_stream(path, VBA_PROJECT_STREAM)?;
+        self.update_dir_stream()
+    }
+
+    /// Removes a module from the project.
+    ///
+    /// This removes the module stream, the module's entry in [`Project::modules`], and
+    /// the module's entries in the *PROJECT* stream. As with [`Project::add_module`],
+    /// the compiled code is discarded, and the *dir* stream is updated.
+    ///
+    /// Designer storages are not removed.
+    pub fn remove_module(&mut self, name: &str) -> Result<()> {
+        let module = self.find_module(name)?;
+        let name = module.name.clone();
+        let path = self.module_stream_path(module);
+        self.container
+            .get_mut()
+            .remove_stream(&path)
+            .map_err(Error::Cfb)?;
+
+        self.update_project_stream(|lines| {
+            let mut in_properties = true;
+            let mut in_workspace = false;
+            lines.retain(|line| {
+                if line.starts_with('[') {
+                    in_properties = false;
+                    in_workspace = line == "[Workspace]";
+                    return true;
+                }
+                let mut parts = line.splitn(2, '=');
+                let key = parts.next().unwrap_or_default();
+                let value = parts.next().unwrap_or_default();
+                if in_properties && MODULE_KEYS.contains(&key) {
+                    // `Document=` values carry a trailing `/&H...` version.
+                    let value = value.split('/').next().unwrap_or_default();
+                    !value.eq_ignore_ascii_case(&name)
+                } else {
+                    !(in_workspace && key.eq_ignore_ascii_case(&name))
+                }
+            });
+        })?;
+
+        self.modules.retain(|module| !module.name_eq(&name));
+        self.discard_performance_cache()
+    }
+
+    /// Replaces a module's source code.
+    ///
+    /// This is the same as [`Project::set_module_source_with`], using [`CacheMode::Keep`].
+    pub fn set_module_source(&mut self, name: &str, source: &str) -> Result<()> {
+        self.set_module_source_with(name, source, CacheMode::Keep)
+    }
+
+    /// Replaces a module's source code, treating the PerformanceCache as specified by
+    /// `cache`.
+    ///
+    /// `source` is encoded using the project's code page, compressed, and written to the
+    /// module's stream. With [`CacheMode::Keep`], the PerformanceCache preceding the
+    /// compressed source code is preserved. Since it may no longer match the source
+    /// code, applications are expected to recompile the project. Note that stale
+    /// compiled code is what [`Project::is_potentially_stomped`] looks for. With
+    /// [`CacheMode::Discard`], the PerformanceCache is removed instead, see
+    /// [`Project::discard_performance_cache`].
+    ///
+    /// Returns [`Error::InvalidOffset`] if the module's stream is shorter than its
+    /// [`Module::text_offset`].
+    ///
+    /// Use [`Project::save`] to write out the modified project.
+    pub fn set_module_source_with(
+        &mut self,
+        name: &str,
+        source: &str,
+        cache: CacheMode,
+    ) -> Result<()> {
+        let module = self.find_module(name)?;
+        let path = self.module_stream_path(module);
+        let offset = module.text_offset;
+
+        let code_page = self.information.code_page;
+        let source = string_to_cp(source, code_page).ok_or(Error::Encoding { code_page })?;
+        let mut data = self.read_stream(&path)?;
+        if data.len() < offset {
+            return Err(Error::InvalidOffset {
+                offset,
+                len: data.len(),
+            });
+        }
+        data.truncate(offset);
+        data.extend(compress(&source));
+        self.write_stream(&path, &data)?;
+
+        match cache {
+            CacheMode::Keep => Ok(()),
+            CacheMode::Discard => self.discard_performance_cache(),
+        }
+    }
+
+    /// Regenerates the *dir* stream from [`Project::information`],
+    /// [`Project::references`], and [`Project::modules`].
+    ///
+    /// Call this after modifying any of these fields, to keep the *dir* stream
+    /// consistent with the module streams. Note that the *PROJECT* stream is not
+    /// updated.
+    ///
+    /// Use [`Project::save`] to write out the modified project.
+    pub fn update_dir_stream(&mut self) -> Result<()> {
+        let data = writer::write_dir_stream(&self.information, &self.references, &self.modules)?;
+        let path = self.project_path(DIR_STREAM_PATH);
+        self.write_stream(path, &compress(&data))
+    }
+
+    fn update_project_stream<G>(&mut self, update: G) -> Result<()>
+    where
+        G: FnOnce(&mut Vec<String>),
+    {
+        const PROJECT_STREAM_PATH: &str = "/PROJECT";
+
+        let code_page = self.information.code_page;
+        let path = self.project_path(PROJECT_STREAM_PATH);
+        let data = self.read_stream(&path)?;
+        let mut lines = cp_to_string(&data, code_page)
+            .lines()
+            .map(str::to_owned)
+            .collect::<Vec<_>>();
+        update(&mut lines);
+
+        let mut text = lines.join("\r\n");
+        text.push_str("\r\n");
+        let data = string_to_cp(&text, code_page).ok_or(Error::Encoding { code_page })?;
+        self.write_stream(path, &data)
+    }
+
+    fn write_stream<P>(&mut self, stream_path: P, data: &[u8]) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        self.container
+            .get_mut()
+            .create_stream(normalize_path(stream_path.as_ref()))
+            .map_err(Error::Cfb)?
+            .write_all(data)
+            .map_err(Error::Cfb)?;
+
+        Ok(())
+    }
+}
+
+impl Project {
+    /// Opens a VBA project from a file.
+    ///
+    /// This is a convenience function that reads the file at `path` into memory and
+    /// passes the data on to [`open_project`]. The file is expected to contain the raw
+    /// binary VBA project (e.g. *vbaProject.bin*), not an Office document.
+    ///
+    /// This function fails on targets without file system support, such as
+    /// `wasm32-unknown-unknown`.
+    ///
+    /// ```rust,no_run
+    /// use ovba::Project;
+    ///
+    /// let project = Project::open_file("vbaProject.bin")?;
+    /// # Ok::<(), ovba::Error>(())
+    /// ```
+    pub fn open_file<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let data = std::fs::read(path)?;
+        open_project(data)
+    }
+
+    /// Returns the CFB data the project was opened from.
+    ///
+    /// These are the exact bytes passed to [`open_project`] (or [`open_project_with`],
+    /// [`ProjectOptions::open`], and [`Project::open_file`]), e.g. the *vbaProject.bin*
+    /// extracted from an Office document. Modifications (such as
+    /// [`Project::set_module_source`]) are not reflected; use [`Project::save`] to get
+    /// the modified data.
+    ///
+    /// The project retains a copy of its input for this purpose. Projects opened through
+    /// [`open_project_from_reader`] don't, and return an empty slice.
+    pub fn raw_bytes(&self) -> &[u8] {
+        &self.raw
+    }
+}
+
+/// Compresses data into a `CompressedContainer`.
+///
+/// This is the inverse of the RLE decompression applied to compressed streams, such
+/// as module source code. The output follows the compression algorithm described in
+/// the [\[MS-OVBA\]][MS-OVBA] specification.
+///
+/// [`decompress`] restores `data`, with one exception mandated by the specification: A
+/// final chunk of fewer than 4096 bytes that doesn't compress is stored raw, padded to
+/// 4096 bytes with `0x00`. The padding is part of the decompressed data.
+///
+/// [MS-OVBA]: https://docs.microsoft.com/en-us/openspecs/office_file_formats/ms-ovba/575462ba-bf67-4190-9fac-c275523c75fc
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    parser::compress(data)
+}
+
+/// Decompresses a `CompressedContainer`.
+///
+/// This is the RLE decompressor used for compressed streams, such as module source code
+/// and the *dir* stream. It is useful for client code that has obtained compressed data
+/// by other means. Client code that operates on a [`Project`] should use
+/// [`Project::decompress_stream_from`] instead.
+///
+/// Malformed input is reported as an [`Error::Decompressor`]. This includes input that
+/// doesn't start with the SignatureByte 0x01, which commonly indicates that `compressed`
+/// starts at the wrong offset.
+///
+/// The size of the decompressed data is not limited, other than by the 4096 bytes a
+/// single chunk can expand to. Use [`decompress_limited`] when processing untrusted
+/// input.
+pub fn decompress(compressed: &[u8]) -> Result<Vec<u8>> {
+    let (_, data) =
+        parser::decompress(compressed).map_err(|e| parser::to_decompressor_error(e, compressed))?;
+    Ok(data)
+}
+
+/// Decompresses a `CompressedContainer` into `out`.
+///
+/// This is the same as [`decompress`], but writes the decompressed data to `out` one
+/// chunk at a time, rather than collecting it in memory. Returns the number of bytes
+/// written.
+///
+/// Malformed input is reported as an [`Error::Decompressor`], failure to write as an
+/// [`Error::Io`]. In either case, data decompressed up to that point has been written
+/// to `out`.
+pub fn decompress_to<W>(compressed: &[u8], out: &mut W) -> Result<u64>
+where
+    W: Write,
+{
+    let to_error = |e| parser::to_decompressor_error(e, compressed);
+    let (mut i, _) = parser::container_signature(compressed).map_err(to_error)?;
+    let mut written = 0_u64;
+    while !i.is_empty() {
+        let (remainder, data) = parser::chunk_parser(i).map_err(to_error)?;
+        out.write_all(&data)?;
+        written += data.len() as u64;
+        i = remainder;
+    }
+    Ok(written)
+}
+
+/// Decompresses a `CompressedContainer`, limiting the decompressed data to `max_len`
+/// bytes.
+///
+/// This is the same as [`decompress`], but fails with an [`Error::Decompressor`] once
+/// the decompressed data would exceed `max_len` bytes. This bounds memory consumption
+/// when processing untrusted input.
+pub fn decompress_limited(compressed: &[u8], max_len: usize) -> Result<Vec<u8>> {
+    let (_, data) = parser::decompress_limited(compressed, max_len)
+        .map_err(|e| parser::to_decompressor_error(e, compressed))?;
+    Ok(data)
+}
+
+/// Decompresses a `CompressedContainer` that may be followed by trailing data.
+///
+/// Unlike [`decompress`], this function doesn't require the `CompressedContainer` to
+/// span the entire input. Decompression stops at the first chunk that cannot be
+/// parsed. On success, the function returns the decompressed data along with the
+/// number of bytes consumed from `compressed`. Client code can compare the latter
+/// against the input length to detect (and decide how to deal with) trailing data.
+pub fn decompress_partial(compressed: &[u8]) -> Result<(Vec<u8>, usize)> {
+    let (remainder, data) = parser::decompress_partial(compressed)
+        .map_err(|e| parser::to_decompressor_error(e, compressed))?;
+    Ok((data, compressed.len() - remainder.len()))
+}
+
+/// Enumerates the procedures declared in a module's source code.
+///
+/// This is a line-based scan for `Sub`, `Function`, and `Property` declarations and
+/// their matching `End` statements, not a full VBA parser. Line continuations are
+/// joined and comments are ignored. A procedure lacking an `End` statement extends up
+/// to the next declaration, or the end of `source`.
+pub fn parse_procedures(source: &str) -> Vec<Procedure> {
+    source::parse_procedures(source)
+}
+
+/// Parses a decompressed *dir* stream.
+///
+/// This operates on a byte slice, independent of the CFB container. Use [`decompress`]
+/// to decompress the raw *dir* stream data first.
+///
+/// Unless `options` calls for lenient parsing, data following the *dir* stream is
+/// reported as [`Error::TrailingData`].
+pub fn parse_dir_stream(data: &[u8], options: ParseOptions) -> Result<ProjectInformation> {
+    parse_dir(data, options.into())
+}
+
+fn parse_dir(data: &[u8], options: ProjectOptions) -> Result<ProjectInformation> {
+    let (remainder, information) =
+        parser::parse_project_information(data, options.parse, options.code_page_override)
+            .map_err(|e| parser::to_error(e, data))?;
+    if !remainder.is_empty() && !options.parse.lenient {
+        return Err(Error::TrailingData {
+            consumed: data.len() - remainder.len(),
+            total: data.len(),
+        });
+    }
+
+    Ok(information)
+}
+
+/// Opens a VBA project.
 ///
 /// This function consumes `raw` and returns a [`Project`] struct on success, populated
 /// with data from the parsed binary input.
+///
+/// `raw` is either a raw binary VBA project (e.g. *vbaProject.bin*), or a CFB that holds
+/// a VBA project in one of its storages. This covers Office documents in the binary file
+/// formats, such as *.doc* or *.xls* files; see [`Project::project_root`].
+///
+/// ZIP packages and MHTML documents aren't supported and fail with
+/// [`Error::UnsupportedFormat`]. For Office Open XML documents (e.g. *.xlsm* files),
+/// extract the *vbaProject.bin* part first. OpenDocument files store StarBasic macros,
+/// which aren't covered by MS-OVBA.
 pub fn open_project(raw: Vec<u8>) -> Result<Project> {
-    let cursor = Cursor::new(raw);
-    let mut container = CompoundFile::open(cursor).map_err(Error::Cfb)?;
+    open_bytes(raw, ProjectOptions::default())
+}
+
+/// Opens a VBA project using the provided parse options.
+///
+/// This is the same as [`open_project`], with parsing behavior controlled by `options`.
+///
+/// ```rust,no_run
+/// use std::fs::read;
+/// use ovba::{open_project_with, ParseOptions};
+///
+/// let data = read("vbaProject.bin")?;
+/// let project = open_project_with(data, ParseOptions { lenient: true })?;
+/// # Ok::<(), ovba::Error>(())
+/// ```
+pub fn open_project_with(raw: Vec<u8>, options: ParseOptions) -> Result<Project> {
+    open_bytes(raw, options.into())
+}
+
+/// Opens a VBA project from a reader.
+///
+/// This is the generalized version of [`open_project`]. It accepts any type that
+/// implements `Read + Seek`, such as a `std::fs::File`, without requiring client code to
+/// read the entire input into memory first.
+///
+/// ```rust,no_run
+/// use std::fs::File;
+/// use ovba::open_project_from_reader;
+///
+/// let file = File::open("vbaProject.bin")?;
+/// let project = open_project_from_reader(file)?;
+/// # Ok::<(), ovba::Error>(())
+/// ```
+///
+/// Data that is already in memory can be borrowed through a `Cursor<&[u8]>`, avoiding
+/// the move (or copy) into a `Vec<u8>` required by [`open_project`]. This is useful if
+/// the data is shared, e.g. held in an `Arc<[u8]>`:
+///
+/// ```rust,no_run
+/// use std::{fs::read, io::Cursor, sync::Arc};
+/// use ovba::open_project_from_reader;
+///
+/// let data: Arc<[u8]> = read("vbaProject.bin")?.into();
+/// let project = open_project_from_reader(Cursor::new(&data[..]))?;
+/// # Ok::<(), ovba::Error>(())
+/// ```
+pub fn open_project_from_reader<R>(reader: R) -> Result<Project<R>>
+where
+    R: Read + Seek,
+{
+    open(reader, ProjectOptions::default())
+}
+
+/// Opens a VBA project from `raw`, retaining a copy for [`Project::raw_bytes`].
+fn open_bytes(raw: Vec<u8>, options: ProjectOptions) -> Result<Project> {
+    let mut project = open(Cursor::new(raw.clone()), options)?;
+    project.raw = raw;
+
+    Ok(project)
+}
+
+fn open<R>(mut reader: R, options: ProjectOptions) -> Result<Project<R>>
+where
+    R: Read + Seek,
+{
+    if let Some(format) = detect_unsupported_format(&mut reader)? {
+        return Err(Error::UnsupportedFormat(format));
+    }
+    let mut container = CompoundFile::open(reader).map_err(Error::Cfb)?;
 
     // Read *dir* stream
-    const DIR_STREAM_PATH: &str = r#"/VBA\dir"#;
+    // A valid CFB without a *dir* stream is not a VBA project
+    let root = find_project_root(&container).ok_or(Error::NotAVbaProject)?;
 
     let mut buffer = Vec::new();
     container
-        .open_stream(DIR_STREAM_PATH)
+        .open_stream(format!("{}{}", root, DIR_STREAM_PATH))
         .map_err(Error::Cfb)?
         .read_to_end(&mut buffer)
         .map_err(Error::Cfb)?;
 
     // Decompress stream
-    let (remainder, buffer) = parser::decompress(&buffer).map_err(|_| Error::Decompressor)?;
-    debug_assert!(remainder.is_empty());
+    let buffer = options.decompress(&buffer)?;
 
     // Parse binary data
-    let (remainder, information) =
-        parser::parse_project_information(&buffer).map_err(|_| Error::Parser)?;
-    debug_assert_eq!(remainder.len(), 0, "Stream not fully consumed");
+    let information = parse_dir(&buffer, options)?;
 
     Ok(Project {
         information: information.information,
         references: information.references,
         modules: information.modules,
         container: RefCell::new(container),
+        root,
+        options,
+        raw: Vec::new(),
     })
 }
 
+/// Returns a description of the input's format, if it is a ZIP package or an MHTML
+/// document rather than a CFB.
+///
+/// OpenDocument packages store the string `mimetype` as the name of their first entry,
+/// followed by its uncompressed contents. Any other ZIP package is most likely an Office
+/// Open XML document. MHTML documents start with MIME headers. The reader's position is
+/// restored before returning.
+fn detect_unsupported_format<R>(reader: &mut R) -> Result<Option<&'static str>>
+where
+    R: Read + Seek,
+{
+    const ZIP_SIGNATURE: &[u8] = b"PK\x03\x04";
+    const ODF_MIME_TYPE: &[u8] = b"mimetypeapplication/vnd.oasis.opendocument.";
+    const MIME_VERSION: &[u8] = b"mime-version:";
+
+    let start = reader.stream_position()?;
+    let mut header = Vec::new();
+    reader.by_ref().take(30 + 64).read_to_end(&mut header)?;
+    reader.seek(SeekFrom::Start(start))?;
+
+    if header.starts_with(ZIP_SIGNATURE) {
+        // The local file header is 30 bytes, followed by the name and the contents of
+        // the (stored) entry, provided that there is no extra field.
+        let is_odf = header.get(26..30) == Some(&[8, 0, 0, 0][..])
+            && header.get(30..30 + ODF_MIME_TYPE.len()) == Some(ODF_MIME_TYPE);
+        return Ok(Some(if is_odf {
+            "OpenDocument package"
+        } else {
+            "ZIP package (e.g. Office Open XML document)"
+        }));
+    }
+
+    // Word and Excel store the VBA project of a single file web page in an ActiveMime
+    // part (*editdata.mso*), which holds a zlib-compressed CFB.
+    let text_start = header
+        .iter()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .unwrap_or(header.len());
+    let is_mhtml = match header[text_start..].get(..MIME_VERSION.len()) {
+        Some(prefix) => prefix.eq_ignore_ascii_case(MIME_VERSION),
+        None => false,
+    };
+    if is_mhtml {
+        return Ok(Some(
+            "MHTML document (the VBA project is stored in a compressed ActiveMime part)",
+        ));
+    }
+
+    Ok(None)
+}
+
+/// Returns the path of the storage holding the VBA project, without a trailing `/`.
+///
+/// The known locations in [`PROJECT_ROOTS`] are tried first. Failing that, all storages
+/// are searched, as VBA projects may also be found in embedded documents.
+fn find_project_root<R>(container: &CompoundFile<R>) -> Option<String> {
+    let is_root = |root: &str| container.is_stream(format!("{}{}", root, DIR_STREAM_PATH));
+    if let Some(root) = PROJECT_ROOTS.iter().find(|root| is_root(root)) {
+        return Some((*root).to_owned());
+    }
+
+    container
+        .walk_storage("/")
+        .ok()?
+        .filter(|entry| entry.is_storage() && !entry.is_root())
+        .map(|entry| normalize_path(entry.path()))
+        .find(|root| is_root(root))
+}
+
+/// Returns `unicode` unless it is empty, and `mbcs` otherwise.
+fn prefer_unicode<'a>(unicode: &'a str, mbcs: &'a str) -> &'a str {
+    if unicode.is_empty() {
+        mbcs
+    } else {
+        unicode
+    }
+}
+
+/// Converts a stream path into the form expected by the `cfb` crate.
+///
+/// The `cfb` crate only splits paths at `/` on all platforms but Windows. Since names of
+/// storages and streams cannot contain `/` or `\`, either is treated as a separator.
+fn normalize_path(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    let mut result = String::with_capacity(path.len() + 1);
+    for name in path.split(&['/', '\\'][..]) {
+        if !name.is_empty() {
+            result.push('/');
+            result.push_str(name);
+        }
+    }
+    if result.is_empty() {
+        result.push('/');
+    }
+
+    result
+}
+
 #[cfg(test)]
 mod tests;
@@ -1,52 +1,108 @@
 #![forbid(unsafe_code)]
 
 use crate::{
-    Information, Module, ModuleType, Reference, ReferenceControl, ReferenceOriginal,
-    ReferenceProject, ReferenceRegistered, SysKind,
+    Information, Module, ModuleType, ParseOptions, ProjectInformation, ProjectProperties,
+    Reference, ReferenceControl, ReferenceOriginal, ReferenceProject, ReferenceRegistered, SysKind,
+    VbaVersion,
 };
 use codepage::to_encoding;
-use encoding_rs::{CoderResult, UTF_16LE};
+use encoding_rs::{CoderResult, EncoderResult, Encoding, BIG5, EUC_KR, GBK, SHIFT_JIS, UTF_16LE};
+use std::convert::TryInto;
+
 use nom::{
     bytes::complete::{tag, take},
     combinator::opt,
-    error::{ErrorKind, ParseError},
+    error::{context, ErrorKind, ParseError},
     multi::length_data,
     number::complete::{le_u16, le_u32, le_u8},
     sequence::{preceded, tuple},
-    Err::Error,
+    Err::{Error, Failure},
     IResult,
 };
 
-// This used to be part of the public interface prior to flattening this out into the
-// [`Project`] struct.
-// TODO: Re-evaluate whether this struct is strictly necessary, or can be removed.
-/// Specifies information for the VBA project, including project information, project
-/// references, and modules.
-#[derive(Debug)]
-pub(crate) struct ProjectInformation {
-    /// Specifies version-independent information for the VBA project.
-    pub information: Information,
-    /// Specifies the external references of the VBA project.
-    pub references: Vec<Reference>,
-    /// Specifies the modules in the project.
-    pub modules: Vec<Module>,
-}
-
-// TODO: Make this error private by translating to a crate-level error type
-//       at the public parser interface.
+/// Parser error type.
+///
+/// This error is internal to the parser implementation. It is translated into a
+/// crate-level error type through [`to_error`] at the parser interface.
+#[derive(Debug, PartialEq)]
+pub(crate) struct FormatError<I> {
+    /// The remaining input at the point where the error was detected.
+    input: I,
+    kind: FormatErrorKind,
+    /// The innermost record context.
+    context: Option<&'static str>,
+}
+
 #[derive(Debug, PartialEq)]
-pub(crate) enum FormatError<I> {
+enum FormatErrorKind {
     UnexpectedValue,
-    Nom(I, ErrorKind),
+    UnexpectedRecord { expected: u16, found: u16 },
+    UnsupportedCodePage(u16),
+    ModuleCountMismatch { declared: u16, found: usize },
+    Nom(ErrorKind),
+}
+
+impl<I> FormatError<I> {
+    fn unexpected_value(input: I) -> Self {
+        FormatError {
+            input,
+            kind: FormatErrorKind::UnexpectedValue,
+            context: None,
+        }
+    }
 }
 
 impl<I> ParseError<I> for FormatError<I> {
     fn from_error_kind(input: I, kind: ErrorKind) -> Self {
-        FormatError::Nom(input, kind)
+        FormatError {
+            input,
+            kind: FormatErrorKind::Nom(kind),
+            context: None,
+        }
     }
     fn append(_: I, _: ErrorKind, other: Self) -> Self {
         other
     }
+    fn add_context(_: I, ctx: &'static str, mut other: Self) -> Self {
+        // Keep the innermost (i.e. most specific) context.
+        other.context.get_or_insert(ctx);
+        other
+    }
+}
+
+/// Translates a parser error into a crate-level error.
+///
+/// `input` is the data the failing parser was invoked on. Offsets are reported relative
+/// to the start of `input`.
+pub(crate) fn to_error(err: nom::Err<FormatError<&[u8]>>, input: &[u8]) -> crate::Error {
+    match err {
+        nom::Err::Error(e) | nom::Err::Failure(e) => {
+            let offset = input.len() - e.input.len();
+            match e.kind {
+                FormatErrorKind::UnexpectedRecord { expected, found } => {
+                    crate::Error::UnexpectedRecord {
+                        expected,
+                        found,
+                        offset,
+                    }
+                }
+                FormatErrorKind::UnsupportedCodePage(code_page) => {
+                    crate::Error::UnsupportedCodePage(code_page)
+                }
+                FormatErrorKind::ModuleCountMismatch { declared, found } => {
+                    crate::Error::ModuleCountMismatch { declared, found }
+                }
+                _ => crate::Error::Parser {
+                    offset,
+                    context: e.context.unwrap_or("dir stream"),
+                },
+            }
+        }
+        nom::Err::Incomplete(_) => crate::Error::Parser {
+            offset: input.len(),
+            context: "dir stream",
+        },
+    }
 }
 
 fn uncompressed_chunk_parser(i: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[u8]>> {
@@ -55,12 +111,13 @@ fn uncompressed_chunk_parser(i: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[
 
 fn compressed_chunk_parser(i: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[u8]>> {
     // Initialize output storage; Chunks are at most 4096 decompressed bytes
-    let mut result = Vec::<u8>::with_capacity(4096);
+    const MAX_DECOMPRESSED_SIZE: usize = 4096;
+    let mut result = Vec::<u8>::with_capacity(MAX_DECOMPRESSED_SIZE);
     // Loop until `i` is depleted
     let mut input = i;
     while !input.is_empty() {
         // Read FlagByte
-        let (i, flag_byte) = le_u8(input)?;
+        let (i, flag_byte) = context("FlagByte", le_u8)(input)?;
         input = i;
         // Loop over bits
         for flag_bit_index in 0..=7 {
@@ -68,31 +125,47 @@ fn compressed_chunk_parser(i: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[u8
             if input.is_empty() {
                 return Ok((input, result));
             }
+            // A chunk cannot decompress to more than 4096 bytes
+            if result.len() >= MAX_DECOMPRESSED_SIZE {
+                return Err(Error(FormatError {
+                    context: Some("DecompressedChunk exceeds 4096 bytes"),
+                    ..FormatError::unexpected_value(input)
+                }));
+            }
             // Determine token type (0b0 == LiteralToken; 0b1 == CopyToken)
             let is_copy_token = (flag_byte & (1 << flag_bit_index)) != 0;
             // Delegate work based on TokenType
             if is_copy_token {
                 // TODO: Move the CopyToken decoder into its own, dedicated parser.
-                let (i, copy_token_raw) = le_u16(input)?;
-                input = i;
+                let (i, copy_token_raw) = context("CopyToken truncated", le_u16)(input)?;
                 // Calculate length/offset masks
-                let diff = result.len();
-                let mut bit_count = 4_usize;
-                while 1 << bit_count < diff {
-                    bit_count += 1;
-                }
+                let bit_count = copy_token_bit_count(result.len());
                 let length_mask = 0xffff_u16 >> bit_count;
                 let offset_mask = !length_mask;
                 // Calculate length/offset
                 let length = ((copy_token_raw & length_mask) + 3) as usize;
                 let offset = (((copy_token_raw & offset_mask) >> (16 - bit_count)) + 1) as usize;
+                // A CopyToken cannot reference data prior to the start of the chunk
+                if offset > result.len() {
+                    return Err(Error(FormatError {
+                        context: Some("CopyToken offset out of range"),
+                        ..FormatError::unexpected_value(input)
+                    }));
+                }
+                if result.len() + length > MAX_DECOMPRESSED_SIZE {
+                    return Err(Error(FormatError {
+                        context: Some("DecompressedChunk exceeds 4096 bytes"),
+                        ..FormatError::unexpected_value(input)
+                    }));
+                }
+                input = i;
                 // Copy `length` bytes starting at index `offset`
                 for index in result.len() - offset..result.len() - offset + length {
                     result.push(result[index]);
                 }
             } else {
                 // LiteralToken -> Copy token from input stream
-                let (i, byte) = le_u8(input)?;
+                let (i, byte) = context("LiteralToken", le_u8)(input)?;
                 input = i;
                 result.push(byte);
             }
@@ -102,20 +175,30 @@ fn compressed_chunk_parser(i: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[u8
     Ok((input, result))
 }
 
-fn chunk_parser(i: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[u8]>> {
+pub(crate) fn chunk_parser(input: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[u8]>> {
     // CompressedChunkHeader (12 bits: size minus 3; 3 bits: 0b110; 1 bit: flag)
     // Delegate to specific parser (compressed/uncompressed) depending on the `flag`
-    let (i, header_raw) = le_u16(i)?;
+    let (i, header_raw) = context("CompressedChunkHeader", le_u16)(input)?;
     // Check header magic (0b110) in bit positions 12..=14
     if (header_raw >> 12) & 0b111 != 0b011 {
-        return Err(Error(FormatError::UnexpectedValue));
+        return Err(Error(FormatError {
+            context: Some("CompressedChunkHeader signature"),
+            ..FormatError::unexpected_value(input)
+        }));
     }
     // Extract compressed/uncompressed flag
     let flag = ((header_raw >> 15) & 0b1) != 0;
     // Extract length
     let length = (header_raw & 0xfff) as usize + 1;
+    // Uncompressed chunks always hold 4096 bytes of data
+    if !flag && length != 4096 {
+        return Err(Error(FormatError {
+            context: Some("Uncompressed chunk size is not 4096 bytes"),
+            ..FormatError::unexpected_value(input)
+        }));
+    }
 
-    let (chunk, remainder) = i.split_at(length);
+    let (remainder, chunk) = context("CompressedChunkData truncated", take(length))(i)?;
     if flag {
         Ok((remainder, compressed_chunk_parser(chunk)?.1))
     } else {
@@ -123,36 +206,276 @@ fn chunk_parser(i: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[u8]>> {
     }
 }
 
+/// Decompress a CompressedContainer, stopping at the first chunk that fails to parse.
+///
+/// Returns the remaining input along with the decompressed data. Callers that require
+/// the entire input to be consumed should use [`decompress`] instead.
+pub(crate) fn decompress_partial(i: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[u8]>> {
+    decompress_partial_limited(i, usize::MAX)
+}
+
+/// Decompress a CompressedContainer, stopping at the first chunk that fails to parse,
+/// and failing if the decompressed data exceeds `max_len` bytes.
+pub(crate) fn decompress_partial_limited(
+    i: &[u8],
+    max_len: usize,
+) -> IResult<&[u8], Vec<u8>, FormatError<&[u8]>> {
+    let (mut i, _) = container_signature(i)?;
+
+    // This is the main `Chunk` parser:
+    // * It parses 0 or more chunks, returning a `Vec<u8>` with decoded content. An
+    //   empty decompressed buffer compresses into a lone signature byte.
+    // * It appends the contents of the most recent `Chunk` to the existing decoded stream.
+    let mut result = Vec::new();
+    loop {
+        let (remainder, data) = match chunk_parser(i) {
+            Ok(chunk) => chunk,
+            Err(Error(_)) => break,
+            Err(e) => return Err(e),
+        };
+        if data.len() > max_len - result.len() {
+            // Exceeding the limit is fatal, as opposed to reaching a malformed chunk.
+            return Err(Failure(FormatError {
+                context: Some("Decompressed data exceeds limit"),
+                ..FormatError::unexpected_value(i)
+            }));
+        }
+        result.extend(data);
+        i = remainder;
+    }
+    Ok((i, result))
+}
+
 /// Decompress a CompressedContainer.
+///
+/// The CompressedContainer is expected to span the entire input.
 pub(crate) fn decompress(i: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[u8]>> {
+    decompress_limited(i, usize::MAX)
+}
+
+/// Decompress a CompressedContainer, failing if the decompressed data exceeds `max_len`
+/// bytes.
+///
+/// The CompressedContainer is expected to span the entire input.
+pub(crate) fn decompress_limited(
+    i: &[u8],
+    max_len: usize,
+) -> IResult<&[u8], Vec<u8>, FormatError<&[u8]>> {
+    let (mut i, _) = container_signature(i)?;
+
+    // Unlike `decompress_partial`, this reports the error of a failing chunk.
+    let mut result = Vec::new();
+    while !i.is_empty() {
+        let (remainder, data) = chunk_parser(i)?;
+        if data.len() > max_len - result.len() {
+            return Err(Error(FormatError {
+                context: Some("Decompressed data exceeds limit"),
+                ..FormatError::unexpected_value(i)
+            }));
+        }
+        result.extend(data);
+        i = remainder;
+    }
+    Ok((i, result))
+}
+
+/// Parses the SignatureByte at the start of a CompressedContainer.
+///
+/// Any value other than 0x01 usually means that the decompressor was pointed at the
+/// wrong offset.
+pub(crate) fn container_signature(i: &[u8]) -> IResult<&[u8], &[u8], FormatError<&[u8]>> {
     const COMPRESSED_CONTAINER_SIGNATURE: &[u8] = &[0x01];
-    let (i, _) = tag(COMPRESSED_CONTAINER_SIGNATURE)(i)?;
+    context(
+        "Invalid CompressedContainer SignatureByte (expected 0x01)",
+        tag(COMPRESSED_CONTAINER_SIGNATURE),
+    )(i)
+}
 
-    // This is the main `Chunk` parser:
-    // * It parses 1 or more chunks, returning a `Vec<u8>` with decoded content.
-    // * It appends the contents of the most recent `Chunk` to the existing decoded stream.
-    // * If all data has been consumed, return an `Ok()` value.
-    nom::combinator::all_consuming(nom::multi::fold_many1(
-        chunk_parser,
-        Vec::new(),
-        |mut acc: Vec<_>, data| {
-            acc.extend(data);
-            acc
+/// Translates a decompressor error into a crate-level error.
+///
+/// `input` is the CompressedContainer the decompressor was invoked on. Offsets are
+/// reported relative to the start of `input`.
+pub(crate) fn to_decompressor_error(
+    err: nom::Err<FormatError<&[u8]>>,
+    input: &[u8],
+) -> crate::Error {
+    match err {
+        nom::Err::Error(e) | nom::Err::Failure(e) => crate::Error::Decompressor {
+            // Chunk parsers operate on sub-slices of `input`, so the offset cannot be
+            // derived from the remaining length.
+            offset: e.input.as_ptr() as usize - input.as_ptr() as usize,
+            reason: e.context.unwrap_or("CompressedContainer"),
         },
-    ))(i)
+        nom::Err::Incomplete(_) => crate::Error::Decompressor {
+            offset: input.len(),
+            reason: "CompressedContainer truncated",
+        },
+    }
+}
+
+/// Returns the number of bits used to encode the offset of a CopyToken, given the
+/// number of bytes decompressed so far in the current chunk.
+fn copy_token_bit_count(difference: usize) -> usize {
+    let mut bit_count = 4_usize;
+    while 1 << bit_count < difference {
+        bit_count += 1;
+    }
+    bit_count
+}
+
+fn compress_chunk(chunk: &[u8], out: &mut Vec<u8>) {
+    // Compressed chunks store at most 4096 bytes of token data. If the tokens don't fit
+    // (or take up more space than the raw data), a raw chunk is emitted instead.
+    const MAX_DATA_SIZE: usize = 4096;
+
+    let mut data = Vec::with_capacity(MAX_DATA_SIZE);
+    let mut current = 0_usize;
+    while current < chunk.len() && data.len() < MAX_DATA_SIZE {
+        // TokenSequence: FlagByte followed by up to 8 tokens
+        let flag_byte_index = data.len();
+        data.push(0_u8);
+        for flag_bit_index in 0..=7 {
+            if current >= chunk.len() {
+                break;
+            }
+            let bit_count = copy_token_bit_count(current);
+            let max_length = (0xffff_usize >> bit_count) + 3;
+            // Find the longest match, closest to the current position
+            let mut best_length = 0_usize;
+            let mut best_candidate = 0_usize;
+            for candidate in (0..current).rev() {
+                let length = chunk[current..]
+                    .iter()
+                    .zip(&chunk[candidate..])
+                    .take(max_length)
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if length > best_length {
+                    best_length = length;
+                    best_candidate = candidate;
+                    if length == max_length {
+                        break;
+                    }
+                }
+            }
+            if best_length >= 3 {
+                // CopyToken
+                let offset = current - best_candidate;
+                let token = (((offset - 1) << (16 - bit_count)) | (best_length - 3)) as u16;
+                data.extend_from_slice(&token.to_le_bytes());
+                data[flag_byte_index] |= 1 << flag_bit_index;
+                current += best_length;
+            } else {
+                // LiteralToken
+                data.push(chunk[current]);
+                current += 1;
+            }
+        }
+    }
+
+    if current < chunk.len() || data.len() > MAX_DATA_SIZE {
+        // Raw chunk; the data is padded to 4096 bytes.
+        let header = 0x3000_u16 | (MAX_DATA_SIZE as u16 - 1);
+        out.extend_from_slice(&header.to_le_bytes());
+        out.extend_from_slice(chunk);
+        out.resize(out.len() + MAX_DATA_SIZE - chunk.len(), 0x00);
+    } else {
+        let header = 0xb000_u16 | (data.len() as u16 - 1);
+        out.extend_from_slice(&header.to_le_bytes());
+        out.extend_from_slice(&data);
+    }
+}
+
+/// Compress data into a CompressedContainer.
+///
+/// The input is split into chunks of 4096 bytes. Each chunk is stored compressed, unless
+/// the compressed representation exceeds 4096 bytes, in which case it is stored raw. Raw
+/// chunks always hold 4096 bytes; a short, final raw chunk is padded with `0x00` bytes
+/// as mandated by the specification.
+pub(crate) fn compress(data: &[u8]) -> Vec<u8> {
+    const COMPRESSED_CONTAINER_SIGNATURE: u8 = 0x01;
+    let mut result = vec![COMPRESSED_CONTAINER_SIGNATURE];
+    for chunk in data.chunks(4096) {
+        compress_chunk(chunk, &mut result);
+    }
+    result
 }
 
 // -------------------------------------------------------------------------
 // -------------------------------------------------------------------------
 
-// Several size fields in the binary format have fixed values.
+// Several size fields in the binary format have fixed values, and reserved fields MUST
+// be 0.
 const U32_FIXED_SIZE_4: &[u8] = &[0x04, 0x00, 0x00, 0x00];
 const U32_FIXED_SIZE_2: &[u8] = &[0x02, 0x00, 0x00, 0x00];
+const U32_RESERVED: &[u8] = &[0x00, 0x00, 0x00, 0x00];
+
+// Record IDs of all records that can appear in the *dir* stream.
+const KNOWN_RECORD_IDS: &[u16] = &[
+    0x0001, 0x0002, 0x0003, 0x0004, 0x0005, 0x0006, 0x0007, 0x0008, 0x0009, 0x000c, 0x000d, 0x000e,
+    0x000f, 0x0010, 0x0013, 0x0014, 0x0016, 0x0019, 0x001a, 0x001c, 0x001e, 0x0021, 0x0022, 0x0025,
+    0x0028, 0x002b, 0x002c, 0x002f, 0x0030, 0x0031, 0x0032, 0x0033, 0x003c, 0x003d, 0x003e, 0x0040,
+    0x0047, 0x0048, 0x004a,
+];
+
+/// Parses the 16-bit ID of a record, and verifies that it matches `expected`.
+///
+/// A mismatch is reported as an unexpected record, located at the start of the ID.
+fn record_id(expected: u16) -> impl Fn(&[u8]) -> IResult<&[u8], u16, FormatError<&[u8]>> {
+    move |i: &[u8]| {
+        let (remainder, found) = le_u16(i)?;
+        if found == expected {
+            Ok((remainder, found))
+        } else {
+            Err(Error(FormatError {
+                input: i,
+                kind: FormatErrorKind::UnexpectedRecord { expected, found },
+                context: None,
+            }))
+        }
+    }
+}
+
+/// Parses an optional record that consists of a 16-bit ID, followed by a reserved 32-bit
+/// field that MUST be 0. Returns whether the record is present.
+///
+/// A record with the expected ID but a non-zero reserved field fails, reported in
+/// `reserved_context`.
+fn flag_record(
+    id: u16,
+    reserved_context: &'static str,
+) -> impl Fn(&[u8]) -> IResult<&[u8], bool, FormatError<&[u8]>> {
+    move |i: &[u8]| match opt(record_id(id))(i)? {
+        (i, Some(_)) => {
+            let (i, _) = context(reserved_context, tag(U32_RESERVED))(i)?;
+            Ok((i, true))
+        }
+        (i, None) => Ok((i, false)),
+    }
+}
+
+/// Skips over records with an unknown ID, if `options` calls for lenient parsing.
+///
+/// Unknown records are assumed to follow the common layout of a 16-bit ID, followed by
+/// a 32-bit size, followed by `size` bytes of data.
+fn skip_unknown_records(i: &[u8], options: ParseOptions) -> IResult<&[u8], (), FormatError<&[u8]>> {
+    let mut i = i;
+    if options.lenient {
+        while let Ok((_, id)) = le_u16::<FormatError<&[u8]>>(i) {
+            if KNOWN_RECORD_IDS.contains(&id) {
+                break;
+            }
+            let (remainder, _) = preceded(le_u16, length_data(le_u32))(i)?;
+            i = remainder;
+        }
+    }
+    Ok((i, ()))
+}
 
 fn parse_syskind(i: &[u8]) -> IResult<&[u8], SysKind, FormatError<&[u8]>> {
-    const SYS_KIND_SIGNATURE: &[u8] = &[0x01, 0x00];
+    const SYS_KIND_SIGNATURE: u16 = 0x0001;
     let (i, sys_kind) = preceded(
-        tuple((tag(SYS_KIND_SIGNATURE), tag(U32_FIXED_SIZE_4))),
+        tuple((record_id(SYS_KIND_SIGNATURE), tag(U32_FIXED_SIZE_4))),
         le_u32,
     )(i)?;
     match sys_kind {
@@ -160,108 +483,134 @@ fn parse_syskind(i: &[u8]) -> IResult<&[u8], SysKind, FormatError<&[u8]>> {
         0x0000_0001 => Ok((i, SysKind::Win32)),
         0x0000_0002 => Ok((i, SysKind::MacOs)),
         0x0000_0003 => Ok((i, SysKind::Win64)),
-        _ => Err(Error(FormatError::UnexpectedValue)),
+        _ => Err(Error(FormatError::unexpected_value(i))),
     }
 }
 
+fn parse_compat_version(i: &[u8]) -> IResult<&[u8], u32, FormatError<&[u8]>> {
+    const COMPAT_VERSION_SIGNATURE: u16 = 0x004a;
+    let (i, compat_version) = preceded(
+        tuple((record_id(COMPAT_VERSION_SIGNATURE), tag(U32_FIXED_SIZE_4))),
+        le_u32,
+    )(i)?;
+    Ok((i, compat_version))
+}
+
 fn parse_lcid(i: &[u8]) -> IResult<&[u8], u32, FormatError<&[u8]>> {
-    const LCID_SIGNATURE: &[u8] = &[0x02, 0x00];
-    let (i, lcid) = preceded(tuple((tag(LCID_SIGNATURE), tag(U32_FIXED_SIZE_4))), le_u32)(i)?;
+    const LCID_SIGNATURE: u16 = 0x0002;
+    let (i, lcid) = preceded(
+        tuple((record_id(LCID_SIGNATURE), tag(U32_FIXED_SIZE_4))),
+        le_u32,
+    )(i)?;
     Ok((i, lcid))
 }
 
 fn parse_lcid_invoke(i: &[u8]) -> IResult<&[u8], u32, FormatError<&[u8]>> {
-    const LCID_INVOKE_SIGNATURE: &[u8] = &[0x14, 0x00];
+    const LCID_INVOKE_SIGNATURE: u16 = 0x0014;
     let (i, lcid_invoke) = preceded(
-        tuple((tag(LCID_INVOKE_SIGNATURE), tag(U32_FIXED_SIZE_4))),
+        tuple((record_id(LCID_INVOKE_SIGNATURE), tag(U32_FIXED_SIZE_4))),
         le_u32,
     )(i)?;
     Ok((i, lcid_invoke))
 }
 
-fn parse_code_page(i: &[u8]) -> IResult<&[u8], u16, FormatError<&[u8]>> {
-    const CODE_PAGE_SIGNATURE: &[u8] = &[0x03, 0x00];
+/// Parses the PROJECTCODEPAGE Record, returning `code_page_override` in place of the
+/// declared code page if set.
+fn parse_code_page(
+    input: &[u8],
+    code_page_override: Option<u16>,
+) -> IResult<&[u8], u16, FormatError<&[u8]>> {
+    const CODE_PAGE_SIGNATURE: u16 = 0x0003;
     let (i, code_page) = preceded(
-        tuple((tag(CODE_PAGE_SIGNATURE), tag(U32_FIXED_SIZE_2))),
+        tuple((record_id(CODE_PAGE_SIGNATURE), tag(U32_FIXED_SIZE_2))),
         le_u16,
-    )(i)?;
+    )(input)?;
+    let code_page = code_page_override.unwrap_or(code_page);
+    // All text in the project is decoded using this code page, so fail early.
+    if encoding_for(code_page).is_none() {
+        return Err(Error(FormatError {
+            input,
+            kind: FormatErrorKind::UnsupportedCodePage(code_page),
+            context: None,
+        }));
+    }
     Ok((i, code_page))
 }
 
 fn parse_name(i: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[u8]>> {
-    const NAME_SIGNATURE: &[u8] = &[0x04, 0x00];
-    let (i, name) = preceded(tag(NAME_SIGNATURE), length_data(le_u32))(i)?;
+    const NAME_SIGNATURE: u16 = 0x0004;
+    let (i, name) = preceded(record_id(NAME_SIGNATURE), length_data(le_u32))(i)?;
     Ok((i, name.to_vec()))
 }
 
 fn parse_doc_string(i: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[u8]>> {
-    const DOC_STRING_SIGNATURE: &[u8] = &[0x05, 0x00];
-    let (i, doc_string) = preceded(tag(DOC_STRING_SIGNATURE), length_data(le_u32))(i)?;
+    const DOC_STRING_SIGNATURE: u16 = 0x0005;
+    let (i, doc_string) = preceded(record_id(DOC_STRING_SIGNATURE), length_data(le_u32))(i)?;
     Ok((i, doc_string.to_vec()))
 }
 
 fn parse_doc_string_unicode(i: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[u8]>> {
-    const DOC_STRING_UNICODE_SIGNATURE: &[u8] = &[0x40, 0x00];
+    const DOC_STRING_UNICODE_SIGNATURE: u16 = 0x0040;
     let (i, doc_string_unicode) =
-        preceded(tag(DOC_STRING_UNICODE_SIGNATURE), length_data(le_u32))(i)?;
+        preceded(record_id(DOC_STRING_UNICODE_SIGNATURE), length_data(le_u32))(i)?;
     // `doc_string_unicode` represents a sequence of UTF-16 code units. If its length is uneven,
     // the input is malformed.
     if (doc_string_unicode.len() & 1_usize) != 0 {
-        Err(Error(FormatError::UnexpectedValue))
+        Err(Error(FormatError::unexpected_value(i)))
     } else {
         Ok((i, doc_string_unicode.to_vec()))
     }
 }
 
 fn parse_help_file_1(i: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[u8]>> {
-    const HELP_FILE_1_SIGNATURE: &[u8] = &[0x06, 0x00];
-    let (i, help_file_1) = preceded(tag(HELP_FILE_1_SIGNATURE), length_data(le_u32))(i)?;
+    const HELP_FILE_1_SIGNATURE: u16 = 0x0006;
+    let (i, help_file_1) = preceded(record_id(HELP_FILE_1_SIGNATURE), length_data(le_u32))(i)?;
     Ok((i, help_file_1.to_vec()))
 }
 
 fn parse_help_file_2(i: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[u8]>> {
-    const HELP_FILE_2_SIGNATURE: &[u8] = &[0x3d, 0x00];
-    let (i, help_file_2) = preceded(tag(HELP_FILE_2_SIGNATURE), length_data(le_u32))(i)?;
+    const HELP_FILE_2_SIGNATURE: u16 = 0x003d;
+    let (i, help_file_2) = preceded(record_id(HELP_FILE_2_SIGNATURE), length_data(le_u32))(i)?;
     Ok((i, help_file_2.to_vec()))
 }
 
 fn parse_help_context(i: &[u8]) -> IResult<&[u8], u32, FormatError<&[u8]>> {
-    const HELP_CONTEXT_SIGNATURE: &[u8] = &[0x07, 0x00];
+    const HELP_CONTEXT_SIGNATURE: u16 = 0x0007;
     let (i, help_context) = preceded(
-        tuple((tag(HELP_CONTEXT_SIGNATURE), tag(U32_FIXED_SIZE_4))),
+        tuple((record_id(HELP_CONTEXT_SIGNATURE), tag(U32_FIXED_SIZE_4))),
         le_u32,
     )(i)?;
     Ok((i, help_context))
 }
 
 fn parse_lib_flags(i: &[u8]) -> IResult<&[u8], u32, FormatError<&[u8]>> {
-    const LIB_FLAGS_SIGNATURE: &[u8] = &[0x08, 0x00];
+    const LIB_FLAGS_SIGNATURE: u16 = 0x0008;
     let (i, lib_flags) = preceded(
-        tuple((tag(LIB_FLAGS_SIGNATURE), tag(U32_FIXED_SIZE_4))),
+        tuple((record_id(LIB_FLAGS_SIGNATURE), tag(U32_FIXED_SIZE_4))),
         le_u32,
     )(i)?;
     Ok((i, lib_flags))
 }
 
 fn parse_version(i: &[u8]) -> IResult<&[u8], (u32, u16), FormatError<&[u8]>> {
-    const VERSION_SIGNATURE: &[u8] = &[0x09, 0x00];
+    const VERSION_SIGNATURE: u16 = 0x0009;
     let (i, version) = preceded(
-        tuple((tag(VERSION_SIGNATURE), tag(U32_FIXED_SIZE_4))),
+        tuple((record_id(VERSION_SIGNATURE), tag(U32_FIXED_SIZE_4))),
         tuple((le_u32, le_u16)),
     )(i)?;
     Ok((i, version))
 }
 
 fn parse_constants(i: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[u8]>> {
-    const CONSTANTS_SIGNATURE: &[u8] = &[0x0c, 0x00];
-    let (i, constants) = preceded(tag(CONSTANTS_SIGNATURE), length_data(le_u32))(i)?;
+    const CONSTANTS_SIGNATURE: u16 = 0x000c;
+    let (i, constants) = preceded(record_id(CONSTANTS_SIGNATURE), length_data(le_u32))(i)?;
     Ok((i, constants.to_vec()))
 }
 
 fn parse_constants_unicode(i: &[u8]) -> IResult<&[u8], Vec<u8>, FormatError<&[u8]>> {
-    const CONSTANTS_UNICODE_SIGNATURE: &[u8] = &[0x3c, 0x00];
+    const CONSTANTS_UNICODE_SIGNATURE: u16 = 0x003c;
     let (i, constants_unicode) =
-        preceded(tag(CONSTANTS_UNICODE_SIGNATURE), length_data(le_u32))(i)?;
+        preceded(record_id(CONSTANTS_UNICODE_SIGNATURE), length_data(le_u32))(i)?;
     Ok((i, constants_unicode.to_vec()))
 }
 
@@ -273,14 +622,17 @@ fn parse_reference_name(
     i: &[u8],
     code_page: u16,
 ) -> IResult<&[u8], Option<String>, FormatError<&[u8]>> {
-    const NAME_SIGNATURE: &[u8] = &[0x16, 0x00];
-    const NAME_UNICODE_SIGNATURE: &[u8] = &[0x3e, 0x00];
+    const NAME_SIGNATURE: u16 = 0x0016;
+    const NAME_UNICODE_SIGNATURE: u16 = 0x003e;
     let (i, name) = opt(tuple((
-        preceded(tag(NAME_SIGNATURE), length_data(le_u32)),
-        preceded(tag(NAME_UNICODE_SIGNATURE), length_data(le_u32)),
+        preceded(record_id(NAME_SIGNATURE), length_data(le_u32)),
+        opt(preceded(
+            record_id(NAME_UNICODE_SIGNATURE),
+            length_data(le_u32),
+        )),
     )))(i)?;
     // name_unicode MUST contain the UTF-16 encoding of name. Can be dropped without
-    // loss of information.
+    // loss of information, and is missing from some files.
     if let Some((name, _name_unicode)) = name {
         let name = cp_to_string(name, code_page);
         Ok((i, Some(name)))
@@ -293,8 +645,8 @@ fn parse_reference_original(
     i: &[u8],
     code_page: u16,
 ) -> IResult<&[u8], String, FormatError<&[u8]>> {
-    const ORIGINAL_SIGNATURE: &[u8] = &[0x33, 0x00];
-    let (i, libid_original) = preceded(tag(ORIGINAL_SIGNATURE), length_data(le_u32))(i)?;
+    const ORIGINAL_SIGNATURE: u16 = 0x0033;
+    let (i, libid_original) = preceded(record_id(ORIGINAL_SIGNATURE), length_data(le_u32))(i)?;
     let libid_original = cp_to_string(libid_original, code_page);
     Ok((i, libid_original))
 }
@@ -313,9 +665,11 @@ fn parse_reference_control(
         _ => (i, None),
     };
 
-    const CONTROL_SIGNATURE: &[u8] = &[0x2f, 0x00];
-    let (i, libid_twiddled) =
-        preceded(tuple((tag(CONTROL_SIGNATURE), le_u32)), length_data(le_u32))(i)?;
+    const CONTROL_SIGNATURE: u16 = 0x002f;
+    let (i, libid_twiddled) = preceded(
+        tuple((record_id(CONTROL_SIGNATURE), le_u32)),
+        length_data(le_u32),
+    )(i)?;
     let libid_twiddled = cp_to_string(libid_twiddled, code_page);
 
     const RESERVED_1: &[u8] = &[0x00, 0x00, 0x00, 0x00];
@@ -333,7 +687,9 @@ fn parse_reference_control(
     let (i, _) = tuple((tag(RESERVED_4), tag(RESERVED_5)))(i)?;
 
     let (i, guid) = take(16_usize)(i)?;
-    let guid = guid.to_vec();
+    let guid = guid
+        .try_into()
+        .map_err(|_| Error(FormatError::unexpected_value(i)))?;
 
     let (i, cookie) = le_u32(i)?;
 
@@ -355,9 +711,9 @@ fn parse_reference_registered(
     i: &[u8],
     code_page: u16,
 ) -> IResult<&[u8], ReferenceRegistered, FormatError<&[u8]>> {
-    const REGISTERED_SIGNATURE: &[u8] = &[0x0d, 0x00];
+    const REGISTERED_SIGNATURE: u16 = 0x000d;
     let (i, libid) = preceded(
-        tuple((tag(REGISTERED_SIGNATURE), le_u32)),
+        tuple((record_id(REGISTERED_SIGNATURE), le_u32)),
         length_data(le_u32),
     )(i)?;
     let libid = cp_to_string(libid, code_page);
@@ -374,7 +730,7 @@ fn parse_reference_project(
     code_page: u16,
 ) -> IResult<&[u8], ReferenceProject, FormatError<&[u8]>> {
     let (i, (libid_absolute, libid_relative, major_version, minor_version)) = tuple((
-        preceded(tuple((tag(&[0x0e, 0x00]), le_u32)), length_data(le_u32)),
+        preceded(tuple((record_id(0x000e), le_u32)), length_data(le_u32)),
         length_data(le_u32),
         le_u32,
         le_u16,
@@ -407,17 +763,21 @@ fn parse_reference(
     i: &[u8],
     code_page: u16,
 ) -> IResult<&[u8], Option<Reference>, FormatError<&[u8]>> {
-    let (i, name) = parse_reference_name(i, code_page)?;
+    let (i, name) = context("REFERENCENAME", |i| parse_reference_name(i, code_page))(i)?;
     // Determine REFERENCE Record variant (or end of array)
-    let (_, id) = le_u16(i)?;
+    let (_, id) = context("REFERENCE", le_u16)(i)?;
     match id {
         0x002f_u16 => {
-            let (i, mut value) = parse_reference_control(i, code_page)?;
+            let (i, mut value) = context("REFERENCECONTROL", |i| {
+                parse_reference_control(i, code_page)
+            })(i)?;
             value.name = name;
             Ok((i, Some(Reference::Control(value))))
         }
         0x0033_u16 => {
-            let (i, libid_original) = parse_reference_original(i, code_page)?;
+            let (i, libid_original) = context("REFERENCEORIGINAL", |i| {
+                parse_reference_original(i, code_page)
+            })(i)?;
             let original = ReferenceOriginal {
                 name,
                 libid_original,
@@ -425,28 +785,37 @@ fn parse_reference(
             Ok((i, Some(Reference::Original(original))))
         }
         0x000d_u16 => {
-            let (i, mut value) = parse_reference_registered(i, code_page)?;
+            let (i, mut value) = context("REFERENCEREGISTERED", |i| {
+                parse_reference_registered(i, code_page)
+            })(i)?;
             value.name = name;
             Ok((i, Some(Reference::Registered(value))))
         }
         0x000e_u16 => {
-            let (i, mut value) = parse_reference_project(i, code_page)?;
+            let (i, mut value) = context("REFERENCEPROJECT", |i| {
+                parse_reference_project(i, code_page)
+            })(i)?;
             value.name = name;
             Ok((i, Some(Reference::Project(value))))
         }
         0x000f_u16 => Ok((i, None)),
-        _ => Err(Error(FormatError::UnexpectedValue)),
+        _ => Err(Error(FormatError {
+            context: Some("REFERENCE"),
+            ..FormatError::unexpected_value(i)
+        })),
     }
 }
 
 fn parse_references(
     i: &[u8],
     code_page: u16,
+    options: ParseOptions,
 ) -> IResult<&[u8], Vec<Reference>, FormatError<&[u8]>> {
     let mut result = Vec::new();
     let mut i = i;
     loop {
-        let (remainder, value) = parse_reference(i, code_page)?;
+        let (remainder, _) = skip_unknown_records(i, options)?;
+        let (remainder, value) = parse_reference(remainder, code_page)?;
         i = remainder;
         if let Some(reference) = value {
             result.push(reference);
@@ -459,72 +828,126 @@ fn parse_references(
 // -------------------------------------------------------------------------
 // -------------------------------------------------------------------------
 
-fn parse_module(i: &[u8], code_page: u16) -> IResult<&[u8], Module, FormatError<&[u8]>> {
+fn parse_module(
+    i: &[u8],
+    code_page: u16,
+    options: ParseOptions,
+) -> IResult<&[u8], Module, FormatError<&[u8]>> {
+    let (i, _) = skip_unknown_records(i, options)?;
     // MODULENAME Record
-    let (i, name) = preceded(tag(&[0x19, 0x00]), length_data(le_u32))(i)?;
+    let (i, name) = context(
+        "MODULENAME",
+        preceded(record_id(0x0019), length_data(le_u32)),
+    )(i)?;
     let name = cp_to_string(name, code_page);
 
+    let (i, _) = skip_unknown_records(i, options)?;
     // (Optional) MODULENAMEUNICODE Record
-    // If present it MUST be the UTF-16 encoding of MODULENAME. It can safely be dropped.
-    let (i, _name_unicode) = opt(preceded(tag(&[0x47, 0x00]), length_data(le_u32)))(i)?;
+    // If present it MUST be the UTF-16 encoding of MODULENAME. It is kept around to look
+    // up modules whose name cannot be represented faithfully in the project's code page.
+    let (i, name_unicode) = context(
+        "MODULENAMEUNICODE",
+        opt(preceded(record_id(0x0047), length_data(le_u32))),
+    )(i)?;
+    let name_unicode = name_unicode.map_or_else(|| name.clone(), utf16_to_string);
 
+    let (i, _) = skip_unknown_records(i, options)?;
     // MODULESTREAMNAME Record
-    // stream_name_unicode MUST be the UTF-16 encoding of stream_name. It can safely be dropped.
-    let (i, (stream_name, _stream_name_unicode)) = tuple((
-        preceded(tag(&[0x1a, 0x00]), length_data(le_u32)),
-        preceded(tag(&[0x32, 0x00]), length_data(le_u32)),
-    ))(i)?;
+    // stream_name_unicode MUST be the UTF-16 encoding of stream_name, but is missing from
+    // some files. It is kept around to
+    // locate the module stream in case stream_name cannot be resolved.
+    let (i, (stream_name, stream_name_unicode)) = context(
+        "MODULESTREAMNAME",
+        tuple((
+            preceded(record_id(0x001a), length_data(le_u32)),
+            opt(preceded(record_id(0x0032), length_data(le_u32))),
+        )),
+    )(i)?;
     let stream_name = cp_to_string(stream_name, code_page);
+    let stream_name_unicode =
+        stream_name_unicode.map_or_else(|| stream_name.clone(), utf16_to_string);
 
+    let (i, _) = skip_unknown_records(i, options)?;
     // MODULEDOCSTRING Record
-    // doc_string_unicode MUST be the UTF-16 encoding of doc_string. It can safely be dropped.
-    let (i, (doc_string, _doc_string_unicode)) = tuple((
-        preceded(tag(&[0x1c, 0x00]), length_data(le_u32)),
-        preceded(tag(&[0x48, 0x00]), length_data(le_u32)),
-    ))(i)?;
+    // doc_string_unicode MUST be the UTF-16 encoding of doc_string, but is missing from some
+    // files.
+    let (i, (doc_string, doc_string_unicode)) = context(
+        "MODULEDOCSTRING",
+        tuple((
+            preceded(record_id(0x001c), length_data(le_u32)),
+            opt(preceded(record_id(0x0048), length_data(le_u32))),
+        )),
+    )(i)?;
     let doc_string = cp_to_string(doc_string, code_page);
+    let doc_string_unicode = doc_string_unicode.map_or_else(String::new, utf16_to_string);
 
+    let (i, _) = skip_unknown_records(i, options)?;
     // MODULEOFFSET Record
-    let (i, text_offset) = preceded(tuple((tag(&[0x31, 0x00]), tag(U32_FIXED_SIZE_4))), le_u32)(i)?;
+    let (i, text_offset) = context(
+        "MODULEOFFSET",
+        preceded(tuple((record_id(0x0031), tag(U32_FIXED_SIZE_4))), le_u32),
+    )(i)?;
     let text_offset = text_offset as _;
 
+    let (i, _) = skip_unknown_records(i, options)?;
     // MODULEHELPCONTEXT Record
-    let (i, help_context) =
-        preceded(tuple((tag(&[0x1e, 0x00]), tag(U32_FIXED_SIZE_4))), le_u32)(i)?;
+    let (i, help_context) = context(
+        "MODULEHELPCONTEXT",
+        preceded(tuple((record_id(0x001e), tag(U32_FIXED_SIZE_4))), le_u32),
+    )(i)?;
 
+    let (i, _) = skip_unknown_records(i, options)?;
     // MODULECOOKIE Record
     // Cookie MUST be ignored on read.
-    let (i, _cookie) = preceded(tuple((tag(&[0x2c, 0x00]), tag(U32_FIXED_SIZE_2))), le_u16)(i)?;
+    let (i, _cookie) = context(
+        "MODULECOOKIE",
+        preceded(tuple((record_id(0x002c), tag(U32_FIXED_SIZE_2))), le_u16),
+    )(i)?;
 
+    let (i, _) = skip_unknown_records(i, options)?;
     // MODULETYPE Record
-    let (i, id) = le_u16(i)?;
-    let module_type = match id {
-        0x0021_u16 => ModuleType::Procedural,
-        0x0022_u16 => ModuleType::DocClsDesigner,
-        _ => return Err(Error(FormatError::UnexpectedValue)),
-    };
-    let (i, _) = tag(&[0x00, 0x00, 0x00, 0x00])(i)?;
-
-    // MODULEREADONLY Record
-    let (i, read_only) = opt(tag(&[0x25, 0x00, 0x00, 0x00, 0x00, 0x00]))(i)?;
-    let read_only = read_only.is_some();
+    // The record ID encodes the module type.
+    let (i, module_type) = context("MODULETYPE", |input| {
+        let (i, id) = le_u16(input)?;
+        match id {
+            0x0021_u16 => Ok((i, ModuleType::Procedural)),
+            0x0022_u16 => Ok((i, ModuleType::DocClsDesigner)),
+            _ => Err(Error(FormatError {
+                context: Some("MODULETYPE"),
+                ..FormatError::unexpected_value(input)
+            })),
+        }
+    })(i)?;
+    let (i, _) = context("MODULETYPE Reserved", tag(U32_RESERVED))(i)?;
+
+    let (i, _) = skip_unknown_records(i, options)?;
+    // (Optional) MODULEREADONLY Record
+    let (i, read_only) = context(
+        "MODULEREADONLY",
+        flag_record(0x0025, "MODULEREADONLY Reserved"),
+    )(i)?;
 
-    // MODULEPRIVATE Record
-    let (i, private) = opt(tag(&[0x28, 0x00, 0x00, 0x00, 0x00, 0x00]))(i)?;
-    let private = private.is_some();
+    let (i, _) = skip_unknown_records(i, options)?;
+    // (Optional) MODULEPRIVATE Record
+    let (i, private) = context(
+        "MODULEPRIVATE",
+        flag_record(0x0028, "MODULEPRIVATE Reserved"),
+    )(i)?;
 
+    let (i, _) = skip_unknown_records(i, options)?;
     // Terminator
-    let (i, _) = tag(&[0x2b, 0x00])(i)?;
-
-    // Reserved
-    let (i, _) = tag(&[0x00, 0x00, 0x00, 0x00])(i)?;
+    let (i, _) = context("MODULE Terminator", record_id(0x002b))(i)?;
+    let (i, _) = context("MODULE Terminator Reserved", tag(U32_RESERVED))(i)?;
 
     Ok((
         i,
         Module {
             name,
+            name_unicode,
             stream_name,
+            stream_name_unicode,
             doc_string,
+            doc_string_unicode,
             text_offset,
             help_context,
             module_type,
@@ -534,18 +957,49 @@ fn parse_module(i: &[u8], code_page: u16) -> IResult<&[u8], Module, FormatError<
     ))
 }
 
-fn parse_modules(i: &[u8], code_page: u16) -> IResult<&[u8], Vec<Module>, FormatError<&[u8]>> {
-    let (i, count) = preceded(tuple((tag(&[0x0f, 0x00]), tag(U32_FIXED_SIZE_2))), le_u16)(i)?;
-    // Cookie MUST be ignored on read.
-    let (i, _cookie) = preceded(tuple((tag(&[0x13, 0x00]), tag(U32_FIXED_SIZE_2))), le_u16)(i)?;
+fn parse_modules(
+    i: &[u8],
+    code_page: u16,
+    options: ParseOptions,
+) -> IResult<&[u8], Vec<Module>, FormatError<&[u8]>> {
+    let (i, count) = context(
+        "PROJECTMODULES",
+        preceded(tuple((record_id(0x000f), tag(U32_FIXED_SIZE_2))), le_u16),
+    )(i)?;
+    // PROJECTCOOKIE Record
+    // The cookie is an implementation-specific value used by the VBA engine to detect
+    // changes to the module list. It MUST be ignored on read.
+    let (i, _) = skip_unknown_records(i, options)?;
+    let (i, _cookie) = context(
+        "PROJECTCOOKIE",
+        preceded(tuple((record_id(0x0013), tag(U32_FIXED_SIZE_2))), le_u16),
+    )(i)?;
 
+    // MODULE Records are parsed up to the dir stream Terminator, rather than trusting
+    // `count`. A disagreement indicates a corrupt (or crafted) dir stream.
+    const MODULE_NAME_SIGNATURE: u16 = 0x0019;
     let mut modules = Vec::new();
     let mut i = i;
-    for _ in 0..count {
-        let (remainder, module) = parse_module(i, code_page)?;
+    loop {
+        let (remainder, _) = skip_unknown_records(i, options)?;
+        match le_u16::<FormatError<&[u8]>>(remainder) {
+            Ok((_, MODULE_NAME_SIGNATURE)) => {}
+            _ => break,
+        }
+        let (remainder, module) = parse_module(remainder, code_page, options)?;
         i = remainder;
         modules.push(module);
     }
+    if modules.len() != count as usize && !options.lenient {
+        return Err(Error(FormatError {
+            input: i,
+            kind: FormatErrorKind::ModuleCountMismatch {
+                declared: count,
+                found: modules.len(),
+            },
+            context: None,
+        }));
+    }
 
     Ok((i, modules))
 }
@@ -553,68 +1007,128 @@ fn parse_modules(i: &[u8], code_page: u16) -> IResult<&[u8], Vec<Module>, Format
 // -------------------------------------------------------------------------
 // -------------------------------------------------------------------------
 
+/// *dir* stream record parser.
+///
+/// Splits the *dir* stream into `(id, data)` pairs, without interpreting any records.
+#[allow(clippy::type_complexity)]
+pub(crate) fn parse_dir_records(
+    i: &[u8],
+) -> IResult<&[u8], Vec<(u16, Vec<u8>)>, FormatError<&[u8]>> {
+    const VERSION_ID: u16 = 0x0009;
+    const VERSION_DATA_SIZE: usize = 6;
+
+    let mut result = Vec::new();
+    let mut i = i;
+    while !i.is_empty() {
+        let (remainder, id) = context("dir stream record", le_u16)(i)?;
+        let (remainder, data) = if id == VERSION_ID {
+            // The PROJECTVERSION Record stores a Reserved field of 4 in place of its size.
+            context("PROJECTVERSION", preceded(le_u32, take(VERSION_DATA_SIZE)))(remainder)?
+        } else {
+            context("dir stream record", length_data(le_u32))(remainder)?
+        };
+        result.push((id, data.to_vec()));
+        i = remainder;
+    }
+
+    Ok((i, result))
+}
+
 /// *dir* stream parser.
+///
+/// With lenient parsing enabled through `options`, records with unknown IDs are skipped.
+/// MBCS strings are decoded using `code_page_override` instead of the declared code page
+/// if set.
 pub(crate) fn parse_project_information(
     i: &[u8],
+    options: ParseOptions,
+    code_page_override: Option<u16>,
 ) -> IResult<&[u8], ProjectInformation, FormatError<&[u8]>> {
-    let (i, sys_kind) = parse_syskind(i)?;
-    let (i, lcid) = parse_lcid(i)?;
-    let (i, lcid_invoke) = parse_lcid_invoke(i)?;
-    let (i, code_page) = parse_code_page(i)?;
-
-    let (i, name) = parse_name(i)?;
+    let (i, _) = skip_unknown_records(i, options)?;
+    let (i, sys_kind) = context("PROJECTSYSKIND", parse_syskind)(i)?;
+    let (i, _) = skip_unknown_records(i, options)?;
+    // (Optional) PROJECTCOMPATVERSION Record
+    let (i, compat_version) = context("PROJECTCOMPATVERSION", opt(parse_compat_version))(i)?;
+    let (i, _) = skip_unknown_records(i, options)?;
+    let (i, lcid) = context("PROJECTLCID", parse_lcid)(i)?;
+    let (i, _) = skip_unknown_records(i, options)?;
+    let (i, lcid_invoke) = context("PROJECTLCIDINVOKE", parse_lcid_invoke)(i)?;
+    let (i, _) = skip_unknown_records(i, options)?;
+    let (i, code_page) = context("PROJECTCODEPAGE", |i| {
+        parse_code_page(i, code_page_override)
+    })(i)?;
+
+    let (i, _) = skip_unknown_records(i, options)?;
+    let (i, name) = context("PROJECTNAME", parse_name)(i)?;
     let name = cp_to_string(&name, code_page);
 
-    let (i, doc_string) = parse_doc_string(i)?;
+    let (i, _) = skip_unknown_records(i, options)?;
+    let (i, doc_string) = context("PROJECTDOCSTRING", parse_doc_string)(i)?;
     let doc_string = cp_to_string(&doc_string, code_page);
 
-    // doc_string_unicode MUST contain the UTF-16 encoding of doc_string. Can safely be dropped.
-    let (i, _doc_string_unicode) = parse_doc_string_unicode(i)?;
+    // doc_string_unicode MUST contain the UTF-16 encoding of doc_string. It is kept around,
+    // as it may represent text that the project's code page cannot.
+    let (i, _) = skip_unknown_records(i, options)?;
+    let (i, doc_string_unicode) = context("PROJECTDOCSTRING", opt(parse_doc_string_unicode))(i)?;
+    let doc_string_unicode = doc_string_unicode.map_or_else(String::new, |d| utf16_to_string(&d));
 
-    let (i, help_file_1) = parse_help_file_1(i)?;
+    let (i, _) = skip_unknown_records(i, options)?;
+    let (i, help_file_1) = context("PROJECTHELPFILEPATH", parse_help_file_1)(i)?;
     let help_file_1 = cp_to_string(&help_file_1, code_page);
 
     // help_file_2 MUST contain the same bytes as help_file_1. Can safely be dropped.
-    let (i, _help_file_2) = parse_help_file_2(i)?;
+    let (i, _) = skip_unknown_records(i, options)?;
+    let (i, _help_file_2) = context("PROJECTHELPFILEPATH", opt(parse_help_file_2))(i)?;
 
-    let (i, help_context) = parse_help_context(i)?;
-    let (i, lib_flags) = parse_lib_flags(i)?;
-    let (i, (version_major, version_minor)) = parse_version(i)?;
+    let (i, _) = skip_unknown_records(i, options)?;
+    let (i, help_context) = context("PROJECTHELPCONTEXT", parse_help_context)(i)?;
+    let (i, _) = skip_unknown_records(i, options)?;
+    let (i, lib_flags) = context("PROJECTLIBFLAGS", parse_lib_flags)(i)?;
+    let (i, _) = skip_unknown_records(i, options)?;
+    let (i, (version_major, version_minor)) = context("PROJECTVERSION", parse_version)(i)?;
 
-    let (i, constants) = parse_constants(i)?;
-    let constants = cp_to_string(&constants, code_page);
+    let (i, _) = skip_unknown_records(i, options)?;
+    // (Optional) PROJECTCONSTANTS Record
+    let (i, constants) = context("PROJECTCONSTANTS", opt(parse_constants))(i)?;
+    let constants = constants.map_or_else(String::new, |c| cp_to_string(&c, code_page));
 
-    // constants_unicode MUST contain the UTF-16 encoding of constants. Can safely be dropped.
-    let (i, _constants_unicode) = parse_constants_unicode(i)?;
+    // constants_unicode MUST contain the UTF-16 encoding of constants. It is kept around for
+    // the same reason as doc_string_unicode.
+    let (i, _) = skip_unknown_records(i, options)?;
+    let (i, constants_unicode) = context("PROJECTCONSTANTS", opt(parse_constants_unicode))(i)?;
+    let constants_unicode = constants_unicode.map_or_else(String::new, |c| utf16_to_string(&c));
 
-    let (i, references) = parse_references(i, code_page)?;
+    let (i, references) = parse_references(i, code_page, options)?;
 
-    let (i, modules) = parse_modules(i, code_page)?;
+    let (i, _) = skip_unknown_records(i, options)?;
+    let (i, modules) = parse_modules(i, code_page, options)?;
 
+    let (i, _) = skip_unknown_records(i, options)?;
     // Terminator
-    let (i, _) = tag(&[0x10, 0x00])(i)?;
+    let (i, _) = context("dir stream Terminator", record_id(0x0010))(i)?;
 
     // Reserved
-    let (i, _) = tag(&[0x00, 0x00, 0x00, 0x00])(i)?;
-
-    debug_assert_eq!(i.len(), 0, "Input not fully read");
+    let (i, _) = context("dir stream Terminator", tag(&[0x00, 0x00, 0x00, 0x00]))(i)?;
 
     Ok((
         i,
         ProjectInformation {
             information: Information {
                 sys_kind,
+                compat_version,
                 lcid,
                 lcid_invoke,
                 code_page,
                 name,
                 doc_string,
+                doc_string_unicode,
                 help_file_1,
                 help_context,
                 lib_flags,
                 version_major,
                 version_minor,
                 constants,
+                constants_unicode,
             },
             references,
             modules,
@@ -625,6 +1139,287 @@ pub(crate) fn parse_project_information(
 // -------------------------------------------------------------------------
 // -------------------------------------------------------------------------
 
+/// Digital signature stream parser.
+///
+/// Returns the `(size, offset)` of the signature buffer, as specified by the
+/// DigSigInfoSerialized structure that follows the DigSigBlob header.
+pub(crate) fn parse_signature_location(
+    i: &[u8],
+) -> IResult<&[u8], (usize, usize), FormatError<&[u8]>> {
+    // DigSigBlob: cbSigInfo, followed by serializedPointer (MUST be 8)
+    let (i, _) = tuple((le_u32, tag(&[0x08, 0x00, 0x00, 0x00])))(i)?;
+    // DigSigInfoSerialized: cbSignature, followed by signatureOffset
+    let (i, (size, offset)) = tuple((le_u32, le_u32))(i)?;
+
+    Ok((i, (size as usize, offset as usize)))
+}
+
+// -------------------------------------------------------------------------
+// -------------------------------------------------------------------------
+
+/// *_VBA_PROJECT* stream header parser.
+///
+/// The remaining input is the PerformanceCache, if present.
+pub(crate) fn parse_vba_version(i: &[u8]) -> IResult<&[u8], VbaVersion, FormatError<&[u8]>> {
+    const RESERVED_1: &[u8] = &[0xcc, 0x61];
+    const RESERVED_2: &[u8] = &[0x00];
+    let (i, (_, version, _, _)) = context(
+        "_VBA_PROJECT",
+        tuple((tag(RESERVED_1), le_u16, tag(RESERVED_2), le_u16)),
+    )(i)?;
+
+    Ok((
+        i,
+        VbaVersion {
+            version,
+            performance_cache: !i.is_empty(),
+        },
+    ))
+}
+
+// -------------------------------------------------------------------------
+// -------------------------------------------------------------------------
+
+/// *PROJECTwm* stream parser.
+///
+/// Returns a list of `(name, name_unicode)` pairs, one for each NAMEMAP Record.
+#[allow(clippy::type_complexity)]
+pub(crate) fn parse_module_name_map(
+    i: &[u8],
+    code_page: u16,
+) -> IResult<&[u8], Vec<(String, String)>, FormatError<&[u8]>> {
+    // A null-terminated array of MBCS characters.
+    fn mbcs_z(i: &[u8]) -> IResult<&[u8], &[u8], FormatError<&[u8]>> {
+        let length = i
+            .iter()
+            .position(|&b| b == 0x00)
+            .ok_or_else(|| Error(FormatError::unexpected_value(i)))?;
+        let (i, name) = take(length)(i)?;
+        let (i, _) = tag(&[0x00])(i)?;
+        Ok((i, name))
+    }
+    // A null-terminated array of UTF-16 code units.
+    fn utf16_z(i: &[u8]) -> IResult<&[u8], &[u8], FormatError<&[u8]>> {
+        let length = i
+            .chunks_exact(2)
+            .position(|c| c == [0x00, 0x00])
+            .ok_or_else(|| Error(FormatError::unexpected_value(i)))?;
+        let (i, name) = take(length * 2)(i)?;
+        let (i, _) = tag(&[0x00, 0x00])(i)?;
+        Ok((i, name))
+    }
+
+    let mut result = Vec::new();
+    let mut i = i;
+    // Module names cannot be empty, so a leading null character starts the Terminator.
+    while !i.starts_with(&[0x00]) {
+        let (remainder, (name, name_unicode)) = context("NAMEMAP", tuple((mbcs_z, utf16_z)))(i)?;
+        result.push((cp_to_string(name, code_page), utf16_to_string(name_unicode)));
+        i = remainder;
+    }
+    let (i, _) = context("PROJECTwm Terminator", tag(&[0x00, 0x00]))(i)?;
+
+    Ok((i, result))
+}
+
+// -------------------------------------------------------------------------
+// -------------------------------------------------------------------------
+
+/// *PROJECT* stream parser.
+///
+/// The *PROJECT* stream is text, so this parser is deliberately forgiving: Lines that
+/// aren't `Key=Value` pairs are skipped, and unknown keys are only reported through
+/// [`ProjectProperties::properties`].
+pub(crate) fn parse_project_properties(text: &str) -> ProjectProperties {
+    enum Section {
+        Properties,
+        HostExtenders,
+        Workspace,
+        Unknown,
+    }
+
+    fn unquote(value: &str) -> String {
+        let value = value.trim();
+        if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+            value[1..value.len() - 1].to_owned()
+        } else {
+            value.to_owned()
+        }
+    }
+
+    let mut result = ProjectProperties::default();
+    let mut section = Section::Properties;
+    for line in text.lines() {
+        if line.starts_with('[') {
+            section = match line {
+                "[Host Extender Info]" => Section::HostExtenders,
+                "[Workspace]" => Section::Workspace,
+                _ => Section::Unknown,
+            };
+            continue;
+        }
+        let (key, value) = match line.find('=') {
+            Some(pos) => (&line[..pos], &line[pos + 1..]),
+            None => continue,
+        };
+        match section {
+            Section::Properties => {}
+            Section::HostExtenders => {
+                result
+                    .host_extenders
+                    .push((key.to_owned(), value.to_owned()));
+                continue;
+            }
+            Section::Workspace => {
+                result.workspace.push((key.to_owned(), value.to_owned()));
+                continue;
+            }
+            Section::Unknown => continue,
+        }
+
+        result.properties.push((key.to_owned(), unquote(value)));
+        match key {
+            "ID" => result.id = Some(unquote(value)),
+            // ProjectDocModule = "Document=" ModuleIdentifier %x2f DocTlibVer
+            "Document" => {
+                let name = value.split('/').next().unwrap_or_default();
+                result.document_modules.push(name.to_owned());
+            }
+            "Module" => result.procedural_modules.push(value.to_owned()),
+            "Class" => result.class_modules.push(value.to_owned()),
+            "BaseClass" => result.designer_modules.push(value.to_owned()),
+            "Package" => result.packages.push(value.to_owned()),
+            "HelpFile" => result.help_file = Some(unquote(value)),
+            "ExeName32" => result.exe_name_32 = Some(unquote(value)),
+            "Name" => result.name = Some(unquote(value)),
+            "HelpContextID" => result.help_context_id = Some(unquote(value)),
+            "Description" => result.description = Some(unquote(value)),
+            "VersionCompatible32" => result.version_compatible_32 = Some(unquote(value)),
+            "CMG" => result.protection_state = Some(unquote(value)),
+            "DPB" => result.password = Some(unquote(value)),
+            "GC" => result.visibility_state = Some(unquote(value)),
+            _ => {}
+        }
+    }
+
+    result
+}
+
+// -------------------------------------------------------------------------
+// -------------------------------------------------------------------------
+
+/// Conditional compilation constants parser.
+///
+/// Constants are declared as `Name = Value` pairs, separated by `:`. Names and values
+/// are trimmed; declarations lacking a `=` or a name are skipped.
+pub(crate) fn parse_conditional_constants(text: &str) -> Vec<(String, String)> {
+    text.split(':')
+        .filter_map(|declaration| {
+            let mut parts = declaration.splitn(2, '=');
+            let name = parts.next()?.trim();
+            let value = parts.next()?.trim();
+            if name.is_empty() {
+                None
+            } else {
+                Some((name.to_owned(), value.to_owned()))
+            }
+        })
+        .collect()
+}
+
+// -------------------------------------------------------------------------
+// -------------------------------------------------------------------------
+
+/// Decrypts a value encrypted using the Data Encryption algorithm (MS-OVBA 2.4.3), such
+/// as the `CMG`, `DPB`, and `GC` properties of the *PROJECT* stream.
+///
+/// `hex` is the hexadecimal text representation of the encrypted data. Offsets in
+/// errors refer to the encrypted bytes.
+pub(crate) fn decrypt_data(hex: &str) -> Result<Vec<u8>, crate::Error> {
+    const VERSION: u8 = 2;
+
+    let hex = hex.as_bytes();
+    if (hex.len() & 1_usize) != 0 || !hex.iter().all(u8::is_ascii_hexdigit) {
+        return Err(crate::Error::Parser {
+            offset: 0,
+            context: "Encrypted data hex digits",
+        });
+    }
+    let digit = |c: u8| (c as char).to_digit(16).unwrap_or_default() as u8;
+    let encrypted = hex
+        .chunks(2)
+        .map(|pair| digit(pair[0]) << 4 | digit(pair[1]))
+        .collect::<Vec<_>>();
+
+    let truncated = |offset: usize| crate::Error::Parser {
+        offset,
+        context: "Encrypted data truncated",
+    };
+    let (seed, version_enc, project_key_enc) = match encrypted[..] {
+        [seed, version_enc, project_key_enc, ..] => (seed, version_enc, project_key_enc),
+        _ => return Err(truncated(encrypted.len())),
+    };
+    if seed ^ version_enc != VERSION {
+        return Err(crate::Error::Parser {
+            offset: 1,
+            context: "Encrypted data version",
+        });
+    }
+
+    // Each byte is decrypted using the two preceding encrypted bytes, and the preceding
+    // decrypted byte.
+    let mut unencrypted_byte_1 = seed ^ project_key_enc;
+    let mut encrypted_byte_1 = project_key_enc;
+    let mut encrypted_byte_2 = version_enc;
+    let mut decrypted = encrypted[3..].iter().map(|&byte_enc| {
+        let byte = byte_enc ^ encrypted_byte_2.wrapping_add(unencrypted_byte_1);
+        encrypted_byte_2 = encrypted_byte_1;
+        encrypted_byte_1 = byte_enc;
+        unencrypted_byte_1 = byte;
+        byte
+    });
+
+    // The seed determines the number of ignored (random) bytes ahead of the length.
+    let ignored_len = usize::from((seed & 6) / 2);
+    let mut header = decrypted.by_ref().take(ignored_len + 4).collect::<Vec<_>>();
+    if header.len() < ignored_len + 4 {
+        return Err(truncated(encrypted.len()));
+    }
+    let len = header.split_off(ignored_len);
+    let len = u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize;
+    let data = decrypted.collect::<Vec<_>>();
+    if data.len() != len {
+        return Err(crate::Error::Parser {
+            offset: 3 + ignored_len,
+            context: "Encrypted data length",
+        });
+    }
+
+    Ok(data)
+}
+
+// -------------------------------------------------------------------------
+// -------------------------------------------------------------------------
+
+/// Maps a code page to an encoding.
+///
+/// In addition to the code pages known to the `codepage` crate, this maps the Mac code
+/// pages for CJK scripts to their Windows equivalents. Win16 projects use Windows code
+/// pages, and need no special treatment.
+fn encoding_for(code_page: u16) -> Option<&'static Encoding> {
+    to_encoding(code_page).or(match code_page {
+        // Japanese (Mac)
+        10001 => Some(SHIFT_JIS),
+        // Traditional Chinese (Mac)
+        10002 => Some(BIG5),
+        // Korean (Mac)
+        10003 => Some(EUC_KR),
+        // Simplified Chinese (Mac)
+        10008 => Some(GBK),
+        _ => None,
+    })
+}
+
 /// # Panics
 ///
 /// This function panics, if:
@@ -636,7 +1431,7 @@ pub(crate) fn parse_project_information(
 /// to a later time, when the set of expected errors and the overall error handling strategy
 /// are better understood.
 pub(crate) fn cp_to_string(data: &[u8], code_page: u16) -> String {
-    let encoding = to_encoding(code_page).expect("Failed to map code page to an encoding.");
+    let encoding = encoding_for(code_page).expect("Failed to map code page to an encoding.");
     let mut decoder = encoding.new_decoder_without_bom_handling();
     // The following returns `None` on overflow. That case is only expected with malformed document
     // input, so let's just panic in this case.
@@ -652,7 +1447,42 @@ pub(crate) fn cp_to_string(data: &[u8], code_page: u16) -> String {
     result
 }
 
-#[allow(dead_code)]
+/// Encodes `text` using `code_page`.
+///
+/// Returns `None` if the code page is unknown, or `text` contains characters that cannot
+/// be represented in the code page.
+pub(crate) fn string_to_cp(text: &str, code_page: u16) -> Option<Vec<u8>> {
+    let encoding = encoding_for(code_page)?;
+    let (data, _, had_errors) = encoding.encode(text);
+    if had_errors {
+        None
+    } else {
+        Some(data.into_owned())
+    }
+}
+
+/// Encodes `text` using `code_page`, replacing characters that cannot be represented with
+/// `?`, as Office does when storing MBCS strings.
+///
+/// Returns `None` if the code page is unknown.
+pub(crate) fn string_to_cp_lossy(text: &str, code_page: u16) -> Option<Vec<u8>> {
+    let mut encoder = encoding_for(code_page)?.new_encoder();
+    let mut result = Vec::new();
+    let mut text = text;
+    loop {
+        let max_length = encoder.max_buffer_length_from_utf8_without_replacement(text.len())?;
+        result.reserve(max_length);
+        let (encoder_result, read) =
+            encoder.encode_from_utf8_to_vec_without_replacement(text, &mut result, true);
+        text = &text[read..];
+        match encoder_result {
+            EncoderResult::InputEmpty => return Some(result),
+            EncoderResult::Unmappable(_) => result.push(b'?'),
+            EncoderResult::OutputFull => {}
+        }
+    }
+}
+
 fn utf16_to_string(data: &[u8]) -> String {
     let mut decoder = UTF_16LE.new_decoder_without_bom_handling();
     let max_length = decoder.max_utf8_buffer_length(data.len()).unwrap();
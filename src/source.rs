@@ -0,0 +1,236 @@
+#![forbid(unsafe_code)]
+
+use crate::{ModuleSource, Procedure, ProcedureKind, Visibility};
+
+/// A logical line of source code, with line continuations joined and comments removed.
+struct LogicalLine {
+    /// The (1-based) number of the first physical line.
+    first: usize,
+    /// The (1-based) number of the last physical line.
+    last: usize,
+    text: String,
+}
+
+/// Returns `line` with a trailing comment removed, and whether a comment was found.
+///
+/// Comments start with `'` outside of string literals, or with a `Rem` statement.
+fn strip_comment(line: &str) -> (&str, bool) {
+    let trimmed = line.trim_start();
+    let is_rem = match (trimmed.get(..3), trimmed.get(3..)) {
+        (Some(keyword), Some(rest)) => {
+            keyword.eq_ignore_ascii_case("rem")
+                && !matches!(rest.chars().next(), Some(c) if !c.is_whitespace())
+        }
+        _ => false,
+    };
+    if is_rem {
+        return ("", true);
+    }
+
+    let mut in_string = false;
+    for (index, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '\'' if !in_string => return (&line[..index], true),
+            _ => {}
+        }
+    }
+    (line, false)
+}
+
+/// Returns `line` without its line continuation sequence (` _`), if present.
+fn strip_continuation(line: &str) -> Option<&str> {
+    let line = line.trim_end();
+    if line == "_" {
+        Some("")
+    } else {
+        line.strip_suffix(" _").or_else(|| line.strip_suffix("\t_"))
+    }
+}
+
+fn logical_lines(source: &str) -> Vec<LogicalLine> {
+    let mut result = Vec::new();
+    let mut current: Option<LogicalLine> = None;
+    // Comments can be continued as well; continuation lines then belong to the comment.
+    let mut in_comment = false;
+
+    for (index, line) in source.lines().enumerate() {
+        let number = index + 1;
+        if in_comment {
+            in_comment = strip_continuation(line).is_some();
+            continue;
+        }
+
+        let (code, has_comment) = strip_comment(line);
+        let mut logical = current.take().unwrap_or(LogicalLine {
+            first: number,
+            last: number,
+            text: String::new(),
+        });
+        logical.last = number;
+        if has_comment {
+            in_comment = strip_continuation(line).is_some();
+            logical.text.push_str(code);
+            result.push(logical);
+        } else if let Some(code) = strip_continuation(code) {
+            logical.text.push_str(code);
+            logical.text.push(' ');
+            current = Some(logical);
+        } else {
+            logical.text.push_str(code);
+            result.push(logical);
+        }
+    }
+    result.extend(current);
+
+    result
+}
+
+/// Returns the kind, visibility, and name of the procedure declared in `line`, if any.
+fn declaration(line: &str) -> Option<(ProcedureKind, Visibility, &str)> {
+    let mut words = line.split_whitespace().peekable();
+
+    let mut visibility = Visibility::Public;
+    while let Some(&word) = words.peek() {
+        if word.eq_ignore_ascii_case("public") {
+            visibility = Visibility::Public;
+        } else if word.eq_ignore_ascii_case("private") {
+            visibility = Visibility::Private;
+        } else if word.eq_ignore_ascii_case("friend") {
+            visibility = Visibility::Friend;
+        } else if !word.eq_ignore_ascii_case("static") {
+            break;
+        }
+        words.next();
+    }
+
+    let keyword = words.next()?;
+    let kind = if keyword.eq_ignore_ascii_case("sub") {
+        ProcedureKind::Sub
+    } else if keyword.eq_ignore_ascii_case("function") {
+        ProcedureKind::Function
+    } else if keyword.eq_ignore_ascii_case("property") {
+        let accessor = words.next()?;
+        if accessor.eq_ignore_ascii_case("get") {
+            ProcedureKind::PropertyGet
+        } else if accessor.eq_ignore_ascii_case("let") {
+            ProcedureKind::PropertyLet
+        } else if accessor.eq_ignore_ascii_case("set") {
+            ProcedureKind::PropertySet
+        } else {
+            return None;
+        }
+    } else {
+        return None;
+    };
+
+    let name = words.next()?.split('(').next().unwrap_or_default();
+    if name.is_empty() {
+        None
+    } else {
+        Some((kind, visibility, name))
+    }
+}
+
+/// Returns whether `line` ends a procedure (`End Sub`, `End Function`, `End Property`).
+fn is_procedure_end(line: &str) -> bool {
+    let mut words = line.split_whitespace();
+    match (words.next(), words.next()) {
+        (Some(end), Some(keyword)) => {
+            end.eq_ignore_ascii_case("end")
+                && ["sub", "function", "property"]
+                    .iter()
+                    .any(|k| keyword.eq_ignore_ascii_case(k))
+        }
+        _ => false,
+    }
+}
+
+/// Enumerates the procedures declared in `source`.
+pub(crate) fn parse_procedures(source: &str) -> Vec<Procedure> {
+    let mut result = Vec::new();
+    let mut current: Option<Procedure> = None;
+    // The last physical line seen, used to close procedures lacking an `End` statement.
+    let mut last = 0;
+
+    for line in logical_lines(source) {
+        if let Some((kind, visibility, name)) = declaration(&line.text) {
+            if let Some(mut procedure) = current.take() {
+                procedure.end_line = last;
+                result.push(procedure);
+            }
+            current = Some(Procedure {
+                name: name.to_owned(),
+                kind,
+                visibility,
+                start_line: line.first,
+                end_line: line.last,
+            });
+        } else if is_procedure_end(&line.text) {
+            if let Some(mut procedure) = current.take() {
+                procedure.end_line = line.last;
+                result.push(procedure);
+            }
+        }
+        last = line.last;
+    }
+    if let Some(mut procedure) = current {
+        procedure.end_line = last;
+        result.push(procedure);
+    }
+
+    result
+}
+
+/// Returns the name and value of an `Attribute` statement.
+fn attribute(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim();
+    let keyword = line.get(..9)?;
+    let rest = line.get(9..)?;
+    if !keyword.eq_ignore_ascii_case("attribute") || !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let mut parts = rest.splitn(2, '=');
+    let name = parts.next()?.trim();
+    let value = parts.next()?.trim();
+    let value = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value);
+
+    Some((name, value))
+}
+
+/// Separates the leading `Attribute` statements in `source` from the code body.
+pub(crate) fn split_attributes(source: &str) -> ModuleSource {
+    let mut attributes = Vec::new();
+    let mut rest = source;
+    while !rest.is_empty() {
+        let (line, next) = match rest.find('\n') {
+            Some(index) => (&rest[..index], &rest[index + 1..]),
+            None => (rest, ""),
+        };
+        match attribute(line) {
+            Some((name, value)) => attributes.push((name.to_owned(), value.to_owned())),
+            None => break,
+        }
+        rest = next;
+    }
+
+    ModuleSource {
+        attributes,
+        code: rest.to_owned(),
+    }
+}
+
+/// Converts CRLF and CR line endings in `source` to LF.
+pub(crate) fn normalize_line_endings(source: &str) -> String {
+    source.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// Returns whether `line` is blank or an `Attribute` statement.
+pub(crate) fn is_blank_or_attribute(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || attribute(line).is_some()
+}
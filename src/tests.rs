@@ -1,4 +1,8 @@
-use super::parser::decompress;
+use super::parser::{
+    compress, cp_to_string, decompress, parse_conditional_constants, parse_dir_records,
+    parse_module_name_map, parse_project_information, parse_project_properties, parse_vba_version,
+};
+use super::ReferenceControl;
 
 #[test]
 fn copy_token_decoder() {
@@ -38,3 +42,2437 @@ fn copy_token_decoder() {
     let contents = decompress(CONTAINER_3).unwrap().1;
     assert_eq!(contents, CONTENTS_3);
 }
+
+#[test]
+fn mbcs_decoder() {
+    // MBCS strings in the *dir* stream are encoded using the project's code page. This
+    // test verifies that non-ASCII input decodes correctly for a selection of single-
+    // and double-byte code pages.
+
+    // "テスト" encoded as Shift-JIS (code page 932)
+    const SHIFT_JIS: &[u8] = b"\x83\x65\x83\x58\x83\x67";
+    assert_eq!(cp_to_string(SHIFT_JIS, 932), "テスト");
+
+    // "Привет" encoded as Windows-1251 (code page 1251)
+    const WINDOWS_1251: &[u8] = b"\xCF\xF0\xE8\xE2\xE5\xF2";
+    assert_eq!(cp_to_string(WINDOWS_1251, 1251), "Привет");
+
+    // "Größe" encoded as Windows-1252 (code page 1252)
+    const WINDOWS_1252: &[u8] = b"Gr\xF6\xDFe";
+    assert_eq!(cp_to_string(WINDOWS_1252, 1252), "Größe");
+}
+
+#[test]
+fn guid_formatting() {
+    // The first three GUID fields are stored in little-endian byte order, the remaining
+    // 8 bytes are stored as is.
+    let control = ReferenceControl {
+        name: None,
+        libid_original: None,
+        libid_twiddled: String::new(),
+        name_extended: None,
+        libid_extended: String::new(),
+        guid: *b"\xE1\x2E\x45\x0D\x8F\xE0\x1A\x10\x85\x2E\x02\x60\x8C\x4D\x0B\xB4",
+        cookie: 0,
+    };
+    assert_eq!(
+        control.guid_string(),
+        "{0D452EE1-E08F-101A-852E-02608C4D0BB4}"
+    );
+}
+
+#[test]
+fn project_properties_parser() {
+    const PROJECT: &str = "ID=\"{00000000-0000-0000-0000-000000000000}\"\r\n\
+        Document=ThisWorkbook/&H00000000\r\n\
+        Document=Sheet1/&H00000000\r\n\
+        Module=Module1\r\n\
+        Class=Class1\r\n\
+        BaseClass=UserForm1\r\n\
+        Package={AC9F2F90-E877-11CE-9F68-00AA00574A4F}\r\n\
+        HelpFile=\"\"\r\n\
+        Name=\"VBAProject\"\r\n\
+        HelpContextID=\"0\"\r\n\
+        VersionCompatible32=\"393222000\"\r\n\
+        CMG=\"CAC8E6E6EAE6EAE6EAE6EAE6\"\r\n\
+        DPB=\"A8AA84846A9F6B9F6B9F\"\r\n\
+        GC=\"9E9CB2B3B3B3B3\"\r\n\
+        \r\n\
+        [Host Extender Info]\r\n\
+        &H00000001={3832D640-CF90-11CF-8E43-00A0C911005A};VBE;&H00000000\r\n\
+        \r\n\
+        [Workspace]\r\n\
+        ThisWorkbook=0, 0, 0, 0, C\r\n\
+        Module1=26, 26, 1142, 573, Z\r\n";
+
+    let properties = parse_project_properties(PROJECT);
+    assert_eq!(
+        properties.id.as_deref(),
+        Some("{00000000-0000-0000-0000-000000000000}")
+    );
+    assert_eq!(properties.document_modules, ["ThisWorkbook", "Sheet1"]);
+    assert_eq!(properties.procedural_modules, ["Module1"]);
+    assert_eq!(properties.class_modules, ["Class1"]);
+    assert_eq!(properties.designer_modules, ["UserForm1"]);
+    assert_eq!(
+        properties.packages,
+        ["{AC9F2F90-E877-11CE-9F68-00AA00574A4F}"]
+    );
+    assert_eq!(properties.help_file.as_deref(), Some(""));
+    assert_eq!(properties.exe_name_32, None);
+    assert_eq!(properties.name.as_deref(), Some("VBAProject"));
+    assert_eq!(properties.help_context_id.as_deref(), Some("0"));
+    assert_eq!(properties.description, None);
+    assert_eq!(
+        properties.protection_state.as_deref(),
+        Some("CAC8E6E6EAE6EAE6EAE6EAE6")
+    );
+    assert_eq!(properties.password.as_deref(), Some("A8AA84846A9F6B9F6B9F"));
+    assert_eq!(
+        properties.visibility_state.as_deref(),
+        Some("9E9CB2B3B3B3B3")
+    );
+    assert_eq!(properties.properties.len(), 14);
+    assert_eq!(properties.host_extenders.len(), 1);
+    assert_eq!(properties.workspace.len(), 2);
+    assert_eq!(properties.workspace[1].0, "Module1");
+}
+
+#[test]
+fn compressor_round_trip() {
+    // An empty buffer compresses into a lone signature byte.
+    assert_eq!(compress(b""), b"\x01");
+    assert!(decompress(b"\x01").unwrap().1.is_empty());
+
+    // Compressing the decompressed contents of a container produced by Excel must
+    // reproduce the container.
+    const CONTAINER: &[u8] = b"\x01\x27\xB0\x00\x41\x74\x74\x72\x69\x62\x75\x74\x00\x65\x20\x56\x42\x5F\x4E\x61\x6D\x00\x65\x20\x3D\x20\x22\x61\x22\x0D\x80\x0A\x61\x62\x63\x64\x65\x66\x06\xF0\x00\x0D\x0A";
+    let contents = decompress(CONTAINER).unwrap().1;
+    assert_eq!(compress(&contents), CONTAINER);
+
+    // Data spanning several chunks, including a highly repetitive one.
+    let mut data = Vec::new();
+    for line in 0..500 {
+        data.extend_from_slice(format!("Debug.Print {}\r\n", line).as_bytes());
+    }
+    data.resize(data.len() + 5000, b'a');
+    let container = compress(&data);
+    assert!(container.len() < data.len());
+    assert_eq!(decompress(&container).unwrap().1, data);
+}
+
+#[test]
+fn decompressor_rejects_malformed_input() {
+    // Chunk header announcing more data than available
+    assert!(crate::decompress(b"\x01\x27\xB0\x00\x41").is_err());
+    // CopyToken referencing data prior to the start of the chunk
+    assert!(crate::decompress(b"\x01\x03\xB0\x02\x41\x00\x10").is_err());
+    // Missing signature byte
+    assert!(crate::decompress(b"\x27\xB0\x00\x41").is_err());
+}
+
+#[test]
+fn decompressor_output_limit() {
+    use super::Error;
+
+    let data = b"Attribute VB_Name = \"Module1\"\r\n".repeat(200);
+    let compressed = compress(&data);
+    assert_eq!(
+        crate::decompress_limited(&compressed, data.len()).unwrap(),
+        data
+    );
+    match crate::decompress_limited(&compressed, data.len() - 1) {
+        Err(Error::Decompressor { reason, .. }) => {
+            assert_eq!(reason, "Decompressed data exceeds limit")
+        }
+        _ => panic!("expected the limit to be exceeded"),
+    }
+}
+
+#[test]
+fn streaming_decompressor() {
+    let data = b"Attribute VB_Name = \"Module1\"\r\n".repeat(200);
+    let compressed = compress(&data);
+    let mut out = Vec::new();
+    let written = crate::decompress_to(&compressed, &mut out).unwrap();
+    assert_eq!(written, data.len() as u64);
+    assert_eq!(out, data);
+}
+
+#[test]
+fn decompressor_error_location() {
+    use super::Error;
+
+    let location = |compressed: &[u8]| match crate::decompress(compressed) {
+        Err(Error::Decompressor { offset, reason }) => (offset, reason),
+        _ => panic!("expected a decompressor error"),
+    };
+    assert_eq!(
+        location(b"\x01\x27\xB0\x00\x41"),
+        (3, "CompressedChunkData truncated")
+    );
+    assert_eq!(
+        location(b"\x01\x03\xB0\x02\x41\x00\x10"),
+        (5, "CopyToken offset out of range")
+    );
+    assert_eq!(
+        location(b"\x27\xB0\x00\x41"),
+        (
+            0,
+            "Invalid CompressedContainer SignatureByte (expected 0x01)"
+        )
+    );
+    assert_eq!(
+        location(b""),
+        (
+            0,
+            "Invalid CompressedContainer SignatureByte (expected 0x01)"
+        )
+    );
+    // A second chunk with an invalid header
+    assert_eq!(
+        location(b"\x01\x01\xB0\x00\x41\x00\x00"),
+        (5, "CompressedChunkHeader signature")
+    );
+    // A CopyToken expanding a chunk beyond 4096 bytes
+    assert_eq!(
+        location(b"\x01\x03\xB0\x02\x61\xFF\x0F"),
+        (5, "DecompressedChunk exceeds 4096 bytes")
+    );
+    // A CopyToken missing its second byte
+    assert_eq!(
+        location(b"\x01\x02\xB0\x02\x61\xFF"),
+        (5, "CopyToken truncated")
+    );
+    // An uncompressed chunk must hold exactly 4096 bytes
+    assert_eq!(
+        location(b"\x01\x02\x30\x61\x62\x63"),
+        (1, "Uncompressed chunk size is not 4096 bytes")
+    );
+    let mut raw = b"\x01\xFF\x3F".to_vec();
+    raw.resize(raw.len() + 4095, 0x61);
+    assert_eq!(location(&raw), (3, "CompressedChunkData truncated"));
+    raw.push(0x61);
+    assert_eq!(crate::decompress(&raw).unwrap(), vec![0x61; 4096]);
+}
+
+#[test]
+fn decompressor_reports_consumed_input() {
+    const CONTAINER: &[u8] = b"\x01\x27\xB0\x00\x41\x74\x74\x72\x69\x62\x75\x74\x00\x65\x20\x56\x42\x5F\x4E\x61\x6D\x00\x65\x20\x3D\x20\x22\x61\x22\x0D\x80\x0A\x61\x62\x63\x64\x65\x66\x06\xF0\x00\x0D\x0A";
+    let mut input = CONTAINER.to_vec();
+    input.extend_from_slice(b"\x00\x00\x00");
+
+    let (data, consumed) = crate::decompress_partial(&input).unwrap();
+    assert_eq!(consumed, CONTAINER.len());
+    assert_eq!(data, crate::decompress(CONTAINER).unwrap());
+    // The strict decompressor rejects trailing data.
+    assert!(crate::decompress(&input).is_err());
+}
+
+/// Returns a *dir* stream record with the common layout of a 16-bit ID, followed by a
+/// 32-bit size, followed by `data`.
+fn record(id: u16, data: &[u8]) -> Vec<u8> {
+    let mut result = id.to_le_bytes().to_vec();
+    result.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    result.extend_from_slice(data);
+    result
+}
+
+/// Returns `text` encoded as UTF-16LE.
+fn utf16(text: &str) -> Vec<u8> {
+    text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+}
+
+/// Returns the records of a minimal *dir* stream, with no references and no modules.
+fn dir_stream_records() -> Vec<Vec<u8>> {
+    vec![
+        record(0x0001, &1_u32.to_le_bytes()),
+        record(0x0002, &0x0409_u32.to_le_bytes()),
+        record(0x0014, &0x0409_u32.to_le_bytes()),
+        record(0x0003, &1252_u16.to_le_bytes()),
+        record(0x0004, b"VBAProject"),
+        [record(0x0005, b""), record(0x0040, b"")].concat(),
+        [record(0x0006, b""), record(0x003d, b"")].concat(),
+        record(0x0007, &0_u32.to_le_bytes()),
+        record(0x0008, &0_u32.to_le_bytes()),
+        // PROJECTVERSION: The Reserved field is fixed at 4, followed by 6 bytes of data
+        b"\x09\x00\x04\x00\x00\x00\x01\x00\x00\x00\x02\x00".to_vec(),
+        [record(0x000c, b""), record(0x003c, b"")].concat(),
+        [
+            record(0x000f, &0_u16.to_le_bytes()),
+            record(0x0013, &0xffff_u16.to_le_bytes()),
+        ]
+        .concat(),
+        record(0x0010, b""),
+    ]
+}
+
+#[test]
+fn lenient_dir_stream_parser() {
+    use super::ParseOptions;
+
+    let strict = ParseOptions::default();
+    let lenient = ParseOptions { lenient: true };
+
+    let records = dir_stream_records();
+    let dir_stream = records.concat();
+    let (_, information) = parse_project_information(&dir_stream, strict, None).unwrap();
+    assert_eq!(information.information.name(), "VBAProject");
+    assert_eq!(information.information.version(), (1, 2));
+    assert_eq!(information.information.compat_version(), None);
+
+    // Insert an unknown record ahead of PROJECTNAME
+    let mut records = dir_stream_records();
+    records.insert(4, b"\xff\x00\x02\x00\x00\x00\xab\xcd".to_vec());
+    let dir_stream = records.concat();
+    assert!(parse_project_information(&dir_stream, strict, None).is_err());
+    let (_, information) = parse_project_information(&dir_stream, lenient, None).unwrap();
+    assert_eq!(information.information.name(), "VBAProject");
+}
+
+#[test]
+fn dir_stream_parser_error_location() {
+    use super::{parser::to_error, Error, ParseOptions};
+
+    // Corrupt the (fixed) Size field of PROJECTCODEPAGE
+    let mut records = dir_stream_records();
+    let offset = records[..3].iter().map(Vec::len).sum::<usize>() + 2;
+    records[3] = b"\x03\x00\x04\x00\x00\x00\xe4\x04".to_vec();
+    let dir_stream = records.concat();
+    let err = parse_project_information(&dir_stream, ParseOptions::default(), None)
+        .map(|_| ())
+        .map_err(|e| to_error(e, &dir_stream))
+        .unwrap_err();
+    match err {
+        Error::Parser {
+            offset: actual,
+            context,
+        } => {
+            assert_eq!(actual, offset);
+            assert_eq!(context, "PROJECTCODEPAGE");
+        }
+        _ => panic!("unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn dir_stream_unexpected_record() {
+    use super::{parser::to_error, Error, ParseOptions};
+
+    // Insert an unknown record ahead of PROJECTNAME
+    let mut records = dir_stream_records();
+    let offset = records[..4].iter().map(Vec::len).sum::<usize>();
+    records.insert(4, b"\xff\x00\x02\x00\x00\x00\xab\xcd".to_vec());
+    let dir_stream = records.concat();
+    let err = parse_project_information(&dir_stream, ParseOptions::default(), None)
+        .map(|_| ())
+        .map_err(|e| to_error(e, &dir_stream))
+        .unwrap_err();
+    match err {
+        Error::UnexpectedRecord {
+            expected,
+            found,
+            offset: actual,
+        } => {
+            assert_eq!(expected, 0x0004);
+            assert_eq!(found, 0x00ff);
+            assert_eq!(actual, offset);
+        }
+        _ => panic!("unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn module_name_map_parser() {
+    let data = b"Module1\0M\0o\0d\0u\0l\0e\x001\0\0\0\xc4\0\xc4\0\0\0\0\0";
+    let (remainder, map) = parse_module_name_map(data, 1252).unwrap();
+    assert!(remainder.is_empty());
+    assert_eq!(
+        map,
+        vec![
+            ("Module1".to_owned(), "Module1".to_owned()),
+            ("\u{c4}".to_owned(), "\u{c4}".to_owned()),
+        ]
+    );
+
+    // Missing Terminator
+    assert!(parse_module_name_map(&data[..data.len() - 2], 1252).is_err());
+}
+
+#[test]
+fn vba_version_parser() {
+    let (_, version) = parse_vba_version(b"\xcc\x61\xff\xff\x00\x03\x00").unwrap();
+    assert_eq!(version.version, 0xffff);
+    assert!(!version.performance_cache);
+
+    let (cache, version) = parse_vba_version(b"\xcc\x61\xb2\x00\x00\x03\x00\x01\x02").unwrap();
+    assert_eq!(version.version, 0x00b2);
+    assert!(version.performance_cache);
+    assert_eq!(cache, b"\x01\x02");
+
+    assert!(parse_vba_version(b"\x00\x00\xff\xff\x00\x03\x00").is_err());
+}
+
+#[test]
+fn not_a_vba_project() {
+    use super::{open_project, Error};
+    use std::io::Cursor;
+
+    let container = cfb::CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+    let raw = container.into_inner().into_inner();
+    match open_project(raw) {
+        Err(Error::NotAVbaProject) => {}
+        Err(e) => panic!("unexpected error: {}", e),
+        Ok(_) => panic!("unexpected success"),
+    }
+}
+
+#[test]
+fn dir_stream_writer_round_trip() {
+    use super::{writer::write_dir_stream, ParseOptions, Reference};
+
+    let libid = b"*\\G{00020430-0000-0000-C000-000000000046}#2.0#0#stdole2.tlb#OLE Automation";
+    let mut registered = (libid.len() as u32).to_le_bytes().to_vec();
+    registered.extend_from_slice(libid);
+    registered.extend_from_slice(&[0x00; 6]);
+    let reference = [
+        record(0x0016, b"stdole"),
+        record(0x003e, &utf16("stdole")),
+        record(0x000d, &registered),
+    ]
+    .concat();
+
+    // A REFERENCECONTROL Record, preceded by its REFERENCEORIGINAL Record
+    let libid_original = b"*\\G{0D452EE1-E08F-101A-852E-02608C4D0BB4}#2.0#0#FM20.DLL#MSForms";
+    let libid_twiddled = b"*\\G{00000000-0000-0000-0000-000000000000}#0.0#0##MSForms";
+    let libid_extended = b"*\\G{7F1AD6F6-B210-4E6B-A4A2-CF3A84E1C4D0}#2.0#0#MSForms.exd#MSForms";
+    let mut twiddled = (libid_twiddled.len() as u32).to_le_bytes().to_vec();
+    twiddled.extend_from_slice(libid_twiddled);
+    twiddled.extend_from_slice(&[0x00; 6]);
+    let mut extended = (libid_extended.len() as u32).to_le_bytes().to_vec();
+    extended.extend_from_slice(libid_extended);
+    extended.extend_from_slice(&[0x00; 6]);
+    extended.extend_from_slice(&[0x11; 16]);
+    extended.extend_from_slice(&7_u32.to_le_bytes());
+    let control = [
+        record(0x0016, b"MSForms"),
+        record(0x003e, &utf16("MSForms")),
+        record(0x0033, libid_original),
+        record(0x002f, &twiddled),
+        record(0x0016, b"MSForms"),
+        record(0x003e, &utf16("MSForms")),
+        record(0x0030, &extended),
+    ]
+    .concat();
+
+    // The Unicode variants hold characters that code page 1252 cannot represent.
+    let module = [
+        record(0x0019, b"Modul?"),
+        record(0x0047, &utf16("Modul\u{3a9}")),
+        record(0x001a, b"Modul?"),
+        record(0x0032, &utf16("Modul\u{3a9}")),
+        record(0x001c, b"Pi is ?"),
+        record(0x0048, &utf16("Pi is \u{3c0}")),
+        record(0x0031, &0x0123_u32.to_le_bytes()),
+        record(0x001e, &0_u32.to_le_bytes()),
+        record(0x002c, &0xffff_u16.to_le_bytes()),
+        record(0x0021, b""),
+        record(0x0028, b""),
+        record(0x002b, b""),
+    ]
+    .concat();
+
+    let mut records = dir_stream_records();
+    records[11] = [
+        record(0x000f, &1_u16.to_le_bytes()),
+        record(0x0013, &0xffff_u16.to_le_bytes()),
+        module,
+    ]
+    .concat();
+    records[10] = [
+        record(0x000c, b"A = 1 ' ?"),
+        record(0x003c, &utf16("A = 1 ' \u{2260}")),
+    ]
+    .concat();
+    records[5] = [
+        record(0x0005, b"? Project"),
+        record(0x0040, &utf16("\u{3a9} Project")),
+    ]
+    .concat();
+    records.insert(11, [reference, control].concat());
+    // PROJECTCOMPATVERSION follows PROJECTSYSKIND
+    records.insert(1, record(0x004a, &2_u32.to_le_bytes()));
+    let dir_stream = records.concat();
+
+    let (_, project) =
+        parse_project_information(&dir_stream, ParseOptions::default(), None).unwrap();
+    assert_eq!(project.information.compat_version(), Some(2));
+    assert_eq!(project.references.len(), 3);
+    match &project.references[..] {
+        [Reference::Registered(_), Reference::Original(original), Reference::Control(control)] => {
+            assert_eq!(original.name(), Some("MSForms"));
+            assert_eq!(original.libid_original().as_bytes(), &libid_original[..]);
+            assert_eq!(control.name(), None);
+            assert_eq!(control.libid_twiddled().as_bytes(), &libid_twiddled[..]);
+            assert_eq!(control.name_extended(), Some("MSForms"));
+            assert_eq!(control.cookie(), 7);
+        }
+        references => panic!("unexpected references: {:?}", references),
+    }
+    assert_eq!(project.information.doc_string_best(), "\u{3a9} Project");
+    assert_eq!(project.information.constants_best(), "A = 1 ' \u{2260}");
+    assert_eq!(project.modules.len(), 1);
+    assert!(project.modules[0].private);
+    assert_eq!(project.modules[0].name_best(), "Modul\u{3a9}");
+    assert_eq!(project.modules[0].doc_string_best(), "Pi is \u{3c0}");
+
+    let written = write_dir_stream(&project.information, &project.references, &project.modules);
+    assert_eq!(written.unwrap(), dir_stream);
+
+    // A renamed module no longer matches its Unicode name, which is written from the new
+    // name instead.
+    let mut modules = project.modules.clone();
+    modules[0].name = "Renamed".to_owned();
+    let written = write_dir_stream(&project.information, &project.references, &modules).unwrap();
+    let (_, renamed) = parse_project_information(&written, ParseOptions::default(), None).unwrap();
+    assert_eq!(renamed.modules[0].name_best(), "Renamed");
+    assert_eq!(renamed.modules[0].doc_string_best(), "Pi is \u{3c0}");
+}
+
+#[test]
+fn digital_signature_location() {
+    use super::{DigitalSignature, SignatureKind};
+
+    let mut data = b"\x20\x00\x00\x00\x08\x00\x00\x00\x04\x00\x00\x00\x2c\x00\x00\x00".to_vec();
+    data.resize(0x2c, 0x00);
+    data.extend_from_slice(b"\x30\x82\x01\x02");
+    let signature = DigitalSignature {
+        kind: SignatureKind::Legacy,
+        data,
+    };
+    assert_eq!(signature.pkcs7(), Some(&b"\x30\x82\x01\x02"[..]));
+
+    // Signature buffer exceeding the stream data
+    let signature = DigitalSignature {
+        kind: SignatureKind::Legacy,
+        data: signature.data[..0x2e].to_vec(),
+    };
+    assert_eq!(signature.pkcs7(), None);
+}
+
+#[test]
+fn digital_signature() {
+    use super::{open_project, SignatureKind};
+
+    let mut project = open_project(vba_project(&[("Module1", "")])).unwrap();
+    assert_eq!(project.digital_signature().unwrap(), None);
+    project
+        .write_stream("/\x05DigitalSignature", b"legacy")
+        .unwrap();
+    project
+        .write_stream("/\x05DigitalSignatureV3", b"v3")
+        .unwrap();
+    let signature = project.digital_signature().unwrap().unwrap();
+    assert_eq!(signature.kind, SignatureKind::V3);
+    assert_eq!(signature.data, b"v3");
+
+    // Signature streams are located next to the *VBA* storage.
+    let mut project =
+        open_project(vba_project_at("/_VBA_PROJECT_CUR", &[("Module1", "")])).unwrap();
+    project
+        .write_stream("/\x05DigitalSignatureAgile", b"root")
+        .unwrap();
+    assert_eq!(project.digital_signature().unwrap(), None);
+    project
+        .write_stream("/_VBA_PROJECT_CUR/\x05DigitalSignatureAgile", b"agile")
+        .unwrap();
+    let signature = project.digital_signature().unwrap().unwrap();
+    assert_eq!(signature.kind, SignatureKind::Agile);
+    assert_eq!(signature.data, b"agile");
+    assert!(project.summary().unwrap().has_signature);
+}
+
+#[test]
+fn content_normalized_data() {
+    use super::writer::write_content_normalized_data;
+
+    let libid: &[u8] = b"*\\G{00020430-0000-0000-C000-000000000046}#2.0#0#stdole2.tlb#OLE";
+    let mut registered = (libid.len() as u32).to_le_bytes().to_vec();
+    registered.extend_from_slice(libid);
+    registered.extend_from_slice(&[0x00; 6]);
+    let absolute = b"*\\DC:\\Other.xlsm";
+    let relative = b"*\\DOther.xlsm";
+    let mut project = (absolute.len() as u32).to_le_bytes().to_vec();
+    project.extend_from_slice(absolute);
+    project.extend_from_slice(&(relative.len() as u32).to_le_bytes());
+    project.extend_from_slice(relative);
+    project.extend_from_slice(&0x1234_5678_u32.to_le_bytes());
+    project.extend_from_slice(&0x0009_u16.to_le_bytes());
+    let twiddled = b"*\\G{00000000-0000-0000-0000-000000000000}#0.0#0##";
+    let mut control = (twiddled.len() as u32).to_le_bytes().to_vec();
+    control.extend_from_slice(twiddled);
+    control.extend_from_slice(&[0x00; 6]);
+
+    let mut records = dir_stream_records();
+    // Text is used as stored, even if the Unicode variant differs.
+    records[10] = [
+        record(0x000c, b"A = ?"),
+        record(0x003c, &utf16("A = \u{3a9}")),
+    ]
+    .concat();
+    records.insert(
+        11,
+        [
+            record(0x0016, b"stdole"),
+            record(0x003e, &utf16("stdole")),
+            record(0x000d, &registered),
+            // Only contributes the bytes preceding the first 0x00 byte.
+            record(0x0016, b"Other"),
+            record(0x000e, &project),
+            // Doesn't contribute.
+            record(0x002f, &control),
+        ]
+        .concat(),
+    );
+    let (_, records) = parse_dir_records(&records.concat()).unwrap();
+
+    // A lone CR doesn't end a line, and is dropped.
+    let sources = vec![
+        b"Attribute VB_Name = \"Module1\"\r\nSub A()\r\n  A = 1\r  B = 2\nEnd Sub".to_vec(),
+        b"attribute VB_Name = \"Module2\"\n".to_vec(),
+    ];
+    let expected = [
+        b"VBAProject".as_ref(),
+        b"A = ?",
+        b"\x0d\x00",
+        &registered,
+        &[0x10],
+        b"Sub A()  A = 1  B = 2End Sub",
+    ]
+    .concat();
+    assert_eq!(write_content_normalized_data(&records, &sources), expected);
+}
+
+#[test]
+fn dir_stream_trailing_data() {
+    use super::{parse_dir_stream, Error, ParseOptions};
+
+    let mut dir_stream = dir_stream_records().concat();
+    let total = dir_stream.len();
+    dir_stream.extend_from_slice(b"\x00\x00");
+    match parse_dir_stream(&dir_stream, ParseOptions::default()) {
+        Err(Error::TrailingData { consumed, .. }) => assert_eq!(consumed, total),
+        _ => panic!("expected trailing data"),
+    }
+    let information = parse_dir_stream(&dir_stream, ParseOptions { lenient: true }).unwrap();
+    assert_eq!(information.information.name(), "VBAProject");
+}
+
+#[test]
+fn dir_record_parser() {
+    let dir_stream = dir_stream_records().concat();
+    let (_, records) = parse_dir_records(&dir_stream).unwrap();
+    let ids = records.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+    assert_eq!(
+        ids,
+        vec![
+            0x0001, 0x0002, 0x0014, 0x0003, 0x0004, 0x0005, 0x0040, 0x0006, 0x003d, 0x0007, 0x0008,
+            0x0009, 0x000c, 0x003c, 0x000f, 0x0013, 0x0010
+        ]
+    );
+    assert_eq!(records[4].1, b"VBAProject");
+    assert_eq!(records[11].1, b"\x01\x00\x00\x00\x02\x00");
+}
+
+#[test]
+fn mac_code_pages() {
+    use super::{parse_dir_stream, Error, ParseOptions};
+
+    // Japanese (Mac)
+    let mut records = dir_stream_records();
+    records[3] = b"\x03\x00\x02\x00\x00\x00\x11\x27".to_vec();
+    let information = parse_dir_stream(&records.concat(), ParseOptions::default()).unwrap();
+    assert_eq!(information.information.code_page, 10001);
+    assert_eq!(cp_to_string(b"\x82\xa0", 10001), "\u{3042}");
+
+    // Unknown code page
+    records[3] = b"\x03\x00\x02\x00\x00\x00\x2a\x00".to_vec();
+    match parse_dir_stream(&records.concat(), ParseOptions::default()) {
+        Err(Error::UnsupportedCodePage(42)) => {}
+        _ => panic!("expected an unsupported code page"),
+    }
+}
+
+#[test]
+fn display_labels() {
+    use super::{ModuleKind, ModuleType, SysKind};
+
+    assert_eq!(SysKind::Win64.to_string(), "64-bit Windows");
+    assert_eq!(ModuleType::Procedural.to_string(), "Standard Module");
+    assert_eq!(ModuleKind::Class.to_string(), "Class Module");
+}
+
+#[test]
+fn procedure_parser() {
+    use super::{parse_procedures, ProcedureKind, Visibility};
+
+    let source = "Attribute VB_Name = \"Module1\"\r
+Option Explicit\r
+' Sub Commented()\r
+Private Sub AutoOpen()\r
+    MsgBox \"End Sub\" ' End Sub\r
+End Sub\r
+\r
+Public Static Function Add(ByVal a As Long, _\r
+                           ByVal b As Long) As Long\r
+    Add = a + b\r
+End Function\r
+Private Declare PtrSafe Function GetTickCount Lib \"kernel32\" () As Long\r
+Friend Property Get Value() As Long\r
+End Property\r
+Sub Unterminated()\r
+";
+    let procedures = parse_procedures(source);
+    let summary = procedures
+        .iter()
+        .map(|p| {
+            (
+                p.name.as_str(),
+                p.kind,
+                p.visibility,
+                p.start_line,
+                p.end_line,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        summary,
+        vec![
+            ("AutoOpen", ProcedureKind::Sub, Visibility::Private, 4, 6),
+            ("Add", ProcedureKind::Function, Visibility::Public, 8, 11),
+            (
+                "Value",
+                ProcedureKind::PropertyGet,
+                Visibility::Friend,
+                13,
+                14
+            ),
+            (
+                "Unterminated",
+                ProcedureKind::Sub,
+                Visibility::Public,
+                15,
+                15
+            ),
+        ]
+    );
+}
+
+#[test]
+fn module_source_attributes() {
+    use super::ModuleSource;
+
+    let source = "Attribute VB_Name = \"Class1\"\r
+Attribute VB_GlobalNameSpace = False\r
+Attribute VB_PredeclaredId = True\r
+Option Explicit\r
+Attribute Value.VB_UserMemId = 0\r
+";
+    let parts = ModuleSource::from(source);
+    assert_eq!(
+        parts.attributes,
+        vec![
+            ("VB_Name".to_owned(), "Class1".to_owned()),
+            ("VB_GlobalNameSpace".to_owned(), "False".to_owned()),
+            ("VB_PredeclaredId".to_owned(), "True".to_owned()),
+        ]
+    );
+    assert_eq!(parts.attribute("vb_predeclaredid"), Some("True"));
+    assert_eq!(parts.attribute("VB_Exposed"), None);
+    assert_eq!(
+        parts.code,
+        "Option Explicit\r\nAttribute Value.VB_UserMemId = 0\r\n"
+    );
+
+    let parts = ModuleSource::from("Sub Main()\r\nEnd Sub\r\n");
+    assert!(parts.attributes.is_empty());
+    assert_eq!(parts.code, "Sub Main()\r\nEnd Sub\r\n");
+}
+
+/// Returns a minimal VBA project as a CFB, holding procedural modules with the
+/// given names and source code.
+fn vba_project(modules: &[(&str, &str)]) -> Vec<u8> {
+    vba_project_at("", modules)
+}
+
+/// Returns a CFB holding a minimal VBA project in the storage at `root`.
+fn vba_project_at(root: &str, modules: &[(&str, &str)]) -> Vec<u8> {
+    use std::io::{Cursor, Write};
+
+    let mut records = dir_stream_records();
+    let terminator = records.pop().unwrap();
+    records.pop();
+    records.push(
+        [
+            record(0x000f, &(modules.len() as u16).to_le_bytes()),
+            record(0x0013, &0xffff_u16.to_le_bytes()),
+        ]
+        .concat(),
+    );
+    let mut project_stream = "ID=\"{00000000-0000-0000-0000-000000000000}\"\r\n".to_owned();
+    for (name, _) in modules {
+        records.push(
+            [
+                record(0x0019, name.as_bytes()),
+                record(0x0047, &utf16(name)),
+                record(0x001a, name.as_bytes()),
+                record(0x0032, &utf16(name)),
+                record(0x001c, b""),
+                record(0x0048, b""),
+                record(0x0031, &0_u32.to_le_bytes()),
+                record(0x001e, &0_u32.to_le_bytes()),
+                record(0x002c, &0xffff_u16.to_le_bytes()),
+                record(0x0021, b""),
+                record(0x002b, b""),
+            ]
+            .concat(),
+        );
+        project_stream.push_str(&format!("Module={}\r\n", name));
+    }
+    records.push(terminator);
+    project_stream.push_str("Name=\"VBAProject\"\r\n");
+
+    let mut file = cfb::CompoundFile::create(Cursor::new(Vec::new())).unwrap();
+    if !root.is_empty() {
+        file.create_storage(root).unwrap();
+    }
+    file.create_storage(format!("{}/VBA", root)).unwrap();
+    let mut write = |path: &str, data: &[u8]| {
+        let path = format!("{}{}", root, path);
+        file.create_stream(path).unwrap().write_all(data).unwrap();
+    };
+    write("/VBA/dir", &compress(&records.concat()));
+    write("/VBA/_VBA_PROJECT", b"\xCC\x61\xFF\xFF\x00\x00\x00");
+    for (name, source) in modules {
+        write(&format!("/VBA/{}", name), &compress(source.as_bytes()));
+    }
+    write("/PROJECT", project_stream.as_bytes());
+
+    file.into_inner().into_inner()
+}
+
+#[test]
+fn stream_path_normalization() {
+    use super::open_project;
+
+    let mut project =
+        open_project(vba_project(&[("Module1", "Sub Main()\r\nEnd Sub\r\n")])).unwrap();
+    let dir = project.read_stream("/VBA/dir").unwrap();
+    for path in &[
+        "VBA/dir",
+        "VBA\\dir",
+        "/VBA\\dir",
+        "\\VBA\\dir",
+        "//VBA//dir",
+    ] {
+        assert_eq!(project.read_stream(path).unwrap(), dir);
+        assert!(project.stream_exists(path));
+    }
+    assert!(!project.stream_exists("VBA"));
+    assert!(!project.stream_exists("VBA/Module2"));
+
+    assert_eq!(
+        project.module_source("Module1").unwrap(),
+        "Sub Main()\r\nEnd Sub\r\n"
+    );
+    project
+        .set_module_source("Module1", "Sub Other()\r\nEnd Sub\r\n")
+        .unwrap();
+    let mut saved = Vec::new();
+    project.save(&mut saved).unwrap();
+    let project = open_project(saved).unwrap();
+    assert_eq!(
+        project.module_source("Module1").unwrap(),
+        "Sub Other()\r\nEnd Sub\r\n"
+    );
+}
+
+#[test]
+fn set_module_source_cache() {
+    use super::{open_project, CacheMode, Error};
+
+    let code = "Sub Main()\r\nEnd Sub\r\n";
+    let mut project = open_project(vba_project(&[("Module1", code), ("Module2", code)])).unwrap();
+    project
+        .write_stream("/VBA/_VBA_PROJECT", b"\xCC\x61\xB2\x00\x00\x00\x00\xAA\xAA")
+        .unwrap();
+    for module in 0..2 {
+        let path = format!("/VBA/Module{}", module + 1);
+        let data = [vec![0xaa; 16], compress(code.as_bytes())].concat();
+        project.write_stream(&path, &data).unwrap();
+        project.modules[module].text_offset = 16;
+    }
+
+    // The PerformanceCache is kept by default.
+    project
+        .set_module_source("Module1", "Sub Other()\r\nEnd Sub\r\n")
+        .unwrap();
+    assert_eq!(project.performance_cache_len("Module1").unwrap(), 16);
+    assert_eq!(project.vba_version().unwrap().version, 0x00b2);
+
+    project
+        .set_module_source_with("Module2", "Sub Next()\r\nEnd Sub\r\n", CacheMode::Discard)
+        .unwrap();
+    let mut saved = Vec::new();
+    project.save(&mut saved).unwrap();
+    let mut project = open_project(saved).unwrap();
+    let version = project.vba_version().unwrap();
+    assert_eq!(version.version, 0xffff);
+    assert!(!version.performance_cache);
+    for module in &project.modules {
+        assert_eq!(module.text_offset, 0);
+    }
+    assert_eq!(
+        project.module_source("Module1").unwrap(),
+        "Sub Other()\r\nEnd Sub\r\n"
+    );
+    assert_eq!(
+        project.module_source("Module2").unwrap(),
+        "Sub Next()\r\nEnd Sub\r\n"
+    );
+    assert!(!project.is_potentially_stomped().unwrap());
+
+    // A text offset past the end of the stream
+    project.modules[0].text_offset = 0x1000;
+    assert!(matches!(
+        project.set_module_source("Module1", code),
+        Err(Error::InvalidOffset { offset: 0x1000, .. })
+    ));
+}
+
+#[test]
+fn module_lookup() {
+    use super::open_project;
+
+    let project = open_project(vba_project(&[
+        ("Module1", "Sub Main()\r\nEnd Sub\r\n"),
+        ("Module2", ""),
+    ]))
+    .unwrap();
+    assert_eq!(project.module("module1").unwrap().name, "Module1");
+    assert_eq!(project.module("MODULE2").unwrap().name, "Module2");
+    assert!(project.module("Module3").is_none());
+    assert_eq!(
+        project.module_source("mODULE1").unwrap(),
+        "Sub Main()\r\nEnd Sub\r\n"
+    );
+}
+
+#[test]
+fn case_insensitive_module_names() {
+    use super::open_project;
+
+    let mut project = open_project(vba_project(&[("Module1", ""), ("Module2", "")])).unwrap();
+    assert!(project.modules[0].name_eq("MODULE1"));
+    assert!(!project.modules[0].name_eq("Module2"));
+
+    project.remove_module("module2").unwrap();
+    let mut saved = Vec::new();
+    project.save(&mut saved).unwrap();
+    let project = open_project(saved).unwrap();
+    assert_eq!(project.modules.len(), 1);
+    assert!(project.module("Module2").is_none());
+    assert!(!project.stream_exists("VBA/Module2"));
+    let properties = project.project_properties().unwrap();
+    assert_eq!(properties.procedural_modules, ["Module1"]);
+}
+
+#[test]
+fn add_and_remove_modules() {
+    use super::{open_project, Error, ModuleType};
+
+    let mut project = open_project(vba_project(&[("Module1", "")])).unwrap();
+    for name in &["", "a/b", "A=B", "A\r\nB", "1abc", "_abc", &"A".repeat(32)] {
+        assert!(
+            matches!(
+                project.add_module(name, "", ModuleType::Procedural),
+                Err(Error::InvalidModuleName(_))
+            ),
+            "{:?}",
+            name
+        );
+    }
+    assert!(matches!(
+        project.add_module("MODULE1", "", ModuleType::Procedural),
+        Err(Error::DuplicateModule(_))
+    ));
+    project.write_stream("/VBA/Orphan", b"").unwrap();
+    assert!(matches!(
+        project.add_module("Orphan", "", ModuleType::Procedural),
+        Err(Error::DuplicateModule(_))
+    ));
+    assert_eq!(project.modules.len(), 1);
+
+    // Adding and removing modules discards the compiled code.
+    let cached = b"\xCC\x61\xB2\x00\x00\x00\x00\xAA";
+    project.write_stream("/VBA/_VBA_PROJECT", cached).unwrap();
+    project
+        .add_module("Class_1", "", ModuleType::DocClsDesigner)
+        .unwrap();
+    assert_eq!(project.vba_version().unwrap().version, 0xffff);
+    assert_eq!(
+        project.project_properties().unwrap().class_modules,
+        ["Class_1"]
+    );
+    project.write_stream("/VBA/_VBA_PROJECT", cached).unwrap();
+    project.remove_module("Class_1").unwrap();
+    assert_eq!(project.vba_version().unwrap().version, 0xffff);
+    assert_eq!(project.modules.len(), 1);
+}
+
+#[test]
+fn stream_reader() {
+    use super::{open_project, Error};
+    use std::io::{Read, Seek, SeekFrom};
+
+    let mut project = open_project(vba_project(&[])).unwrap();
+    let data = project.read_stream("/VBA/_VBA_PROJECT").unwrap();
+
+    let mut reader = project.open_stream_reader("VBA\\_VBA_PROJECT").unwrap();
+    let mut header = [0_u8; 2];
+    reader.read_exact(&mut header).unwrap();
+    assert_eq!(header, [0xCC, 0x61]);
+    reader.seek(SeekFrom::Start(4)).unwrap();
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest).unwrap();
+    assert_eq!(rest, &data[4..]);
+    drop(reader);
+
+    assert!(matches!(
+        project.open_stream_reader("/VBA/missing"),
+        Err(Error::MissingStream { .. })
+    ));
+}
+
+#[test]
+fn missing_stream() {
+    use super::{open_project, Error};
+
+    let project = open_project(vba_project(&[])).unwrap();
+    match project.read_stream("VBA\\missing") {
+        Err(Error::MissingStream { path }) => assert_eq!(path, "/VBA/missing"),
+        _ => panic!("expected a MissingStream error"),
+    }
+    // Storages aren't streams.
+    assert!(matches!(
+        project.read_stream("/VBA"),
+        Err(Error::MissingStream { .. })
+    ));
+}
+
+#[test]
+fn module_help_topic() {
+    use super::{open_project, Error};
+
+    let project = open_project(vba_project(&[("Module1", "")])).unwrap();
+    assert_eq!(project.information.help_file(), "");
+    assert_eq!(project.help_topic("Module1").unwrap(), None);
+    match project.help_topic("Module2") {
+        Err(Error::ModuleNotFound(name)) => assert_eq!(name, "Module2"),
+        _ => panic!("expected a ModuleNotFound error"),
+    }
+}
+
+#[test]
+fn optional_dir_stream_records() {
+    use super::ParseOptions;
+
+    // Drop the Unicode variants, HelpFile2, and PROJECTCONSTANTS
+    let mut records = dir_stream_records();
+    records[5] = record(0x0005, b"Description");
+    records[6] = record(0x0006, b"");
+    records.remove(10);
+    // A module lacking MODULESTREAMNAME's and MODULEDOCSTRING's Unicode variants
+    let module = [
+        record(0x0019, b"Module1"),
+        record(0x001a, b"Module1"),
+        record(0x001c, b""),
+        record(0x0031, &0_u32.to_le_bytes()),
+        record(0x001e, &0_u32.to_le_bytes()),
+        record(0x002c, &0xffff_u16.to_le_bytes()),
+        record(0x0021, b""),
+        record(0x002b, b""),
+    ]
+    .concat();
+    records[10] = [
+        record(0x000f, &1_u16.to_le_bytes()),
+        record(0x0013, &0xffff_u16.to_le_bytes()),
+        module,
+    ]
+    .concat();
+    let dir_stream = records.concat();
+
+    let (_, project) =
+        parse_project_information(&dir_stream, ParseOptions::default(), None).unwrap();
+    assert_eq!(project.information.doc_string(), "Description");
+    assert_eq!(project.information.constants(), "");
+    assert_eq!(project.modules.len(), 1);
+    assert!(project.modules[0].name_eq("Module1"));
+    assert_eq!(project.modules[0].stream_name, "Module1");
+
+    // Mandatory records are still required
+    records.remove(4);
+    let dir_stream = records.concat();
+    assert!(parse_project_information(&dir_stream, ParseOptions::default(), None).is_err());
+}
+
+#[test]
+fn conditional_constants_parser() {
+    assert_eq!(
+        parse_conditional_constants("DEBUG = 1 : TRACE=-1:  Win64 =0 "),
+        vec![
+            ("DEBUG".to_owned(), "1".to_owned()),
+            ("TRACE".to_owned(), "-1".to_owned()),
+            ("Win64".to_owned(), "0".to_owned()),
+        ]
+    );
+    assert!(parse_conditional_constants("").is_empty());
+    assert!(parse_conditional_constants(" : = 1 : NoValue").is_empty());
+}
+
+#[test]
+fn list_matching_entries() {
+    use super::open_project;
+
+    let project = open_project(vba_project(&[("Module1", ""), ("Module2", "")])).unwrap();
+    let entries = project.list_matching("/VBA/Module").unwrap();
+    assert_eq!(
+        entries,
+        vec![
+            ("Module1".to_owned(), "/VBA/Module1".to_owned()),
+            ("Module2".to_owned(), "/VBA/Module2".to_owned()),
+        ]
+    );
+    assert!(project.list_matching("/VBA/__SRP_").unwrap().is_empty());
+    assert_eq!(project.list_matching("").unwrap(), project.list().unwrap());
+}
+
+#[test]
+fn project_root_lookup() {
+    use super::{open_project, Error, ModuleType};
+
+    for root in &["/Macros", "/_VBA_PROJECT_CUR"] {
+        let mut project =
+            open_project(vba_project_at(root, &[("Module1", "Sub Main()\r\n")])).unwrap();
+        assert_eq!(project.module_source("Module1").unwrap(), "Sub Main()\r\n");
+        assert_eq!(
+            project.project_properties().unwrap().procedural_modules,
+            ["Module1"]
+        );
+        assert_eq!(project.vba_version().unwrap().version, 0xffff);
+        assert!(!project.dir_records().unwrap().is_empty());
+        assert_eq!(project.project_root(), *root);
+
+        project
+            .add_module("Module2", "", ModuleType::Procedural)
+            .unwrap();
+        assert!(project.stream_exists(&format!("{}/VBA/Module2", root)));
+        let mut saved = Vec::new();
+        project.save(&mut saved).unwrap();
+        let project = open_project(saved).unwrap();
+        assert_eq!(project.modules.len(), 2);
+    }
+
+    // Projects in other storages are found by searching the entire CFB
+    let project = open_project(vba_project_at("/ObjectPool", &[("Module1", "")])).unwrap();
+    assert_eq!(project.project_root(), "/ObjectPool");
+    assert_eq!(project.module_source("Module1").unwrap(), "");
+    let project = open_project(vba_project(&[])).unwrap();
+    assert_eq!(project.project_root(), "/");
+
+    let file = cfb::CompoundFile::create(std::io::Cursor::new(Vec::new())).unwrap();
+    match open_project(file.into_inner().into_inner()) {
+        Err(Error::NotAVbaProject) => {}
+        _ => panic!("expected a NotAVbaProject error"),
+    }
+}
+
+#[test]
+fn module_count_mismatch() {
+    use super::{parse_dir_stream, Error, ParseOptions};
+
+    // PROJECTMODULES declares 2 modules, no MODULE Records follow
+    let mut records = dir_stream_records();
+    records[11] = [
+        record(0x000f, &2_u16.to_le_bytes()),
+        record(0x0013, &0xffff_u16.to_le_bytes()),
+    ]
+    .concat();
+    let dir_stream = records.concat();
+
+    match parse_dir_stream(&dir_stream, ParseOptions::default()) {
+        Err(Error::ModuleCountMismatch { declared, found }) => {
+            assert_eq!((declared, found), (2, 0))
+        }
+        _ => panic!("expected a ModuleCountMismatch error"),
+    }
+    let information = parse_dir_stream(&dir_stream, ParseOptions { lenient: true }).unwrap();
+    assert!(information.modules.is_empty());
+}
+
+#[test]
+fn module_text_offset() {
+    use super::{open_project, Error};
+
+    let mut project = open_project(vba_project(&[("Module1", "")])).unwrap();
+    // A module stream with a (fake) PerformanceCache ahead of the source code
+    let mut stream = b"\xAA\xBB\x01\xCC".to_vec();
+    stream.extend(compress(b"Sub Main()\r\nEnd Sub\r\n"));
+    project.write_stream("/VBA/Module1", &stream).unwrap();
+
+    project.modules[0].text_offset = 4;
+    assert_eq!(
+        project.module_source("Module1").unwrap(),
+        "Sub Main()\r\nEnd Sub\r\n"
+    );
+    assert_eq!(project.performance_cache_len("Module1").unwrap(), 4);
+
+    for &offset in &[0, 3, stream.len()] {
+        project.modules[0].text_offset = offset;
+        match project.module_source_raw("Module1") {
+            Err(Error::InvalidTextOffset { module, offset: o }) => {
+                assert_eq!((module.as_str(), o), ("Module1", offset))
+            }
+            _ => panic!("expected an InvalidTextOffset error"),
+        }
+    }
+}
+
+#[test]
+fn compressor_round_trip_edge_cases() {
+    // A xorshift generator, to produce reproducible, incompressible data
+    let mut state = 0x2545_f491_u32;
+    let mut random = move || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state as u8
+    };
+
+    // Around chunk boundaries, and the largest chunk holding 4096 bytes of literals
+    let sizes = [0, 1, 2, 3, 17, 3640, 3641, 4095, 4096, 4097];
+    for &size in &sizes {
+        let incompressible = (0..size).map(|_| random()).collect::<Vec<_>>();
+        let repetitive = vec![b'a'; size];
+        let pattern = (0..size).map(|i| b"Sub X()\r\n"[i % 9]).collect::<Vec<_>>();
+        // Runs of random length, forcing flag transitions at varying positions
+        let mut mixed = Vec::with_capacity(size);
+        while mixed.len() < size {
+            let byte = random();
+            let length = (random() % 12) as usize + 1;
+            mixed.resize(mixed.len() + length.min(size - mixed.len()), byte);
+        }
+
+        for data in &[incompressible, repetitive, pattern, mixed] {
+            let container = compress(data);
+            let decompressed = crate::decompress(&container).unwrap();
+            // A short, final chunk that doesn't compress is stored raw, padded to 4096
+            // bytes. The padding shows up in the decompressed data.
+            let (contents, padding) = decompressed.split_at(size.min(decompressed.len()));
+            assert_eq!(contents, &data[..], "size {}", size);
+            assert!(padding.iter().all(|&b| b == 0x00), "size {}", size);
+            assert!(decompressed.len() <= data.chunks(4096).count() * 4096);
+            // Chunks hold at most 4096 bytes of data, plus a 2-byte header
+            assert!(container.len() <= 1 + data.chunks(4096).count() * 4098);
+        }
+    }
+}
+
+#[test]
+fn unsupported_formats() {
+    use super::{open_project, Error};
+
+    // Local file header of a stored entry with the given name and contents.
+    let zip = |name: &str, contents: &str| {
+        let mut data = b"PK\x03\x04".to_vec();
+        data.resize(26, 0);
+        data.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        data.extend_from_slice(&[0, 0]);
+        data.extend_from_slice(name.as_bytes());
+        data.extend_from_slice(contents.as_bytes());
+        data
+    };
+
+    let ods = zip("mimetype", "application/vnd.oasis.opendocument.spreadsheet");
+    match open_project(ods) {
+        Err(Error::UnsupportedFormat(format)) => assert!(format.starts_with("OpenDocument")),
+        _ => panic!("expected an UnsupportedFormat error"),
+    }
+    let xlsm = zip("[Content_Types].xml", "<?xml");
+    match open_project(xlsm) {
+        Err(Error::UnsupportedFormat(format)) => assert!(format.starts_with("ZIP")),
+        _ => panic!("expected an UnsupportedFormat error"),
+    }
+    let mht = b"\r\nMIME-Version: 1.0\r\nContent-Type: multipart/related; boundary=\"----=_NextPart\"\r\n";
+    match open_project(mht.to_vec()) {
+        Err(Error::UnsupportedFormat(format)) => assert!(format.starts_with("MHTML")),
+        _ => panic!("expected an UnsupportedFormat error"),
+    }
+    assert!(matches!(open_project(b"PK".to_vec()), Err(Error::Cfb(_))));
+    assert!(matches!(open_project(b"MIME".to_vec()), Err(Error::Cfb(_))));
+    assert!(open_project(vba_project(&[])).is_ok());
+}
+
+#[test]
+fn module_source_hash() {
+    use super::{hash::digest, open_project, HashAlgorithm};
+
+    let project = open_project(vba_project(&[
+        (
+            "Module1",
+            "Attribute VB_Name = \"Module1\"\r\nSub Main()\r\nEnd Sub\r\n",
+        ),
+        (
+            "Module2",
+            "Attribute VB_Name = \"Module2\"\nSub Main()\nEnd Sub\n",
+        ),
+        ("Module3", "Sub Other()\r\nEnd Sub\r\n"),
+    ]))
+    .unwrap();
+    let hash = project
+        .module_source_hash("Module1", HashAlgorithm::Sha256)
+        .unwrap();
+    assert_eq!(
+        hash,
+        digest(HashAlgorithm::Sha256, b"Sub Main()\nEnd Sub\n")
+    );
+    assert_eq!(
+        project
+            .module_source_hash("Module2", HashAlgorithm::Sha256)
+            .unwrap(),
+        hash
+    );
+    assert_ne!(
+        project
+            .module_source_hash("Module3", HashAlgorithm::Sha256)
+            .unwrap(),
+        hash
+    );
+    let md5 = project
+        .module_source_hash("Module1", HashAlgorithm::Md5)
+        .unwrap();
+    assert_eq!(md5, digest(HashAlgorithm::Md5, b"Sub Main()\nEnd Sub\n"));
+    assert!(project
+        .module_source_hash("Module4", HashAlgorithm::Sha256)
+        .is_err());
+}
+
+#[test]
+fn project_options() {
+    use super::{Error, ProjectOptions};
+
+    let source = "Sub Main()\r\nEnd Sub\r\n".repeat(100);
+    let data = vba_project(&[("Module1", source.as_str())]);
+
+    let project = ProjectOptions::new().open(data.clone()).unwrap();
+    assert_eq!(project.module_source("Module1").unwrap(), source);
+
+    for &lenient in &[false, true] {
+        let project = ProjectOptions::new()
+            .lenient(lenient)
+            .max_decompressed(source.len() - 1)
+            .open(data.clone())
+            .unwrap();
+        match project.module_source("Module1") {
+            Err(Error::Decompressor { reason, .. }) => {
+                assert_eq!(reason, "Decompressed data exceeds limit")
+            }
+            _ => panic!("expected a Decompressor error"),
+        }
+        let project = ProjectOptions::new()
+            .lenient(lenient)
+            .max_decompressed(source.len())
+            .open_reader(std::io::Cursor::new(data.clone()))
+            .unwrap();
+        assert_eq!(project.module_source("Module1").unwrap(), source);
+
+        // The limit applies to the dir stream, too.
+        assert!(matches!(
+            ProjectOptions::new()
+                .lenient(lenient)
+                .max_decompressed(16)
+                .open(data.clone()),
+            Err(Error::Decompressor { .. })
+        ));
+    }
+}
+
+#[test]
+fn code_page_override() {
+    use super::{parse_dir_stream, Error, ParseOptions, ProjectOptions};
+
+    // PROJECTCODEPAGE 0, with a Windows-1252 encoded PROJECTNAME
+    let mut records = dir_stream_records();
+    records[3] = b"\x03\x00\x02\x00\x00\x00\x00\x00".to_vec();
+    records[4] = b"\x04\x00\x04\x00\x00\x00Caf\xe9".to_vec();
+    let dir_stream = records.concat();
+    assert!(matches!(
+        parse_dir_stream(&dir_stream, ParseOptions::default()),
+        Err(Error::UnsupportedCodePage(0))
+    ));
+    let (_, information) =
+        parse_project_information(&dir_stream, ParseOptions::default(), Some(1252)).unwrap();
+    assert_eq!(information.information.code_page, 1252);
+    assert_eq!(information.information.name(), "Café");
+
+    let data = vba_project(&[("Module1", "")]);
+    let project = ProjectOptions::new()
+        .code_page_override(Some(1251))
+        .open(data.clone())
+        .unwrap();
+    assert_eq!(project.information.code_page, 1251);
+    assert!(matches!(
+        ProjectOptions::new().code_page_override(Some(0)).open(data),
+        Err(Error::UnsupportedCodePage(0))
+    ));
+}
+
+#[test]
+fn module_is_empty() {
+    use super::{open_project, Error, ProjectOptions};
+
+    // A line of code past the first chunk, following a line spanning the chunk boundary.
+    let long = format!(
+        "Attribute VB_Name = \"Module3\"\r\n\r\nAttribute VB_Description = \"{}\"\r\n  Dim x\r\n",
+        "x".repeat(5000)
+    );
+    let blank = format!("{}\r\n", " ".repeat(8000));
+    let project = open_project(vba_project(&[
+        ("Module1", ""),
+        ("Module2", "Attribute VB_Name = \"Module2\"\r\n\r\n  \r\n"),
+        ("Module3", long.as_str()),
+        ("Module4", blank.as_str()),
+        ("Module5", "Sub Main()\r\nEnd Sub"),
+    ]))
+    .unwrap();
+    assert!(project.module_is_empty("Module1").unwrap());
+    assert!(project.module_is_empty("Module2").unwrap());
+    assert!(!project.module_is_empty("Module3").unwrap());
+    assert!(project.module_is_empty("Module4").unwrap());
+    assert!(!project.module_is_empty("Module5").unwrap());
+    assert!(project.module_is_empty("Module6").is_err());
+
+    // A line without a line break is given up on past 64 KiB.
+    let unterminated = " ".repeat(0x2_0000);
+    let data = vba_project(&[("Module1", unterminated.as_str()), ("Module2", " ")]);
+    let project = open_project(data.clone()).unwrap();
+    assert!(!project.module_is_empty("Module1").unwrap());
+    assert!(project.module_is_empty("Module2").unwrap());
+
+    // The decompressed size limit applies.
+    let project = ProjectOptions::new()
+        .max_decompressed(0x1000)
+        .open(data)
+        .unwrap();
+    match project.module_is_empty("Module1") {
+        Err(Error::Decompressor { reason, .. }) => {
+            assert_eq!(reason, "Decompressed data exceeds limit")
+        }
+        _ => panic!("expected a decompressor error"),
+    }
+    assert!(project.module_is_empty("Module2").unwrap());
+}
+
+#[test]
+fn data_model_equality() {
+    use super::open_project;
+
+    let data = vba_project(&[("Module1", "Sub Main()\r\nEnd Sub\r\n")]);
+    let first = open_project(data.clone()).unwrap();
+    let second = open_project(data).unwrap();
+    assert_eq!(first.information, second.information);
+    assert_eq!(first.references, second.references);
+    assert_eq!(first.modules, second.modules);
+
+    let mut module = first.modules[0].clone();
+    assert_eq!(module, first.modules[0]);
+    module.text_offset += 1;
+    assert_ne!(module, first.modules[0]);
+    assert_eq!(
+        first.module_source_parts("Module1").unwrap(),
+        second.module_source_parts("Module1").unwrap().clone()
+    );
+}
+
+#[test]
+fn project_diff() {
+    use super::{open_project, ModuleDiff};
+
+    let old = open_project(vba_project(&[
+        ("Module1", "Sub Main()\r\nEnd Sub\r\n"),
+        ("Module2", "Sub Main()\r\nEnd Sub\r\n"),
+        ("Module3", ""),
+        ("Module5", ""),
+    ]))
+    .unwrap();
+    let mut new = open_project(vba_project(&[
+        ("module1", "Sub Main()\nEnd Sub\n"),
+        ("Module2", "Sub Other()\r\nEnd Sub\r\n"),
+        ("Module4", ""),
+        ("Module5", ""),
+    ]))
+    .unwrap();
+    new.modules[3].doc_string = "Changed".to_owned();
+
+    let diff = old.diff(&new).unwrap();
+    assert_eq!(diff.added, ["Module4"]);
+    assert_eq!(diff.removed, ["Module3"]);
+    assert_eq!(
+        diff.modified,
+        [
+            ModuleDiff {
+                name: "Module2".to_owned(),
+                source_changed: true,
+                metadata_changed: false,
+            },
+            ModuleDiff {
+                name: "Module5".to_owned(),
+                source_changed: false,
+                metadata_changed: true,
+            },
+        ]
+    );
+    assert!(!diff.is_empty());
+    assert!(old.diff(&old).unwrap().is_empty());
+}
+
+#[test]
+fn module_compressed_source() {
+    use super::{compress, decompress, open_project, ParseOptions, ProjectOptions};
+    use std::io::{Cursor, Write};
+
+    let source = "Sub Main()\r\nEnd Sub\r\n";
+    let compressed = compress(source.as_bytes());
+    let data = vba_project(&[("Module1", source)]);
+
+    let project = open_project(data.clone()).unwrap();
+    let result = project.module_compressed_source("Module1").unwrap();
+    assert_eq!(result, compressed);
+    assert_eq!(decompress(&result).unwrap(), source.as_bytes());
+    assert!(project.module_compressed_source("Module2").is_err());
+
+    // Append trailing data to the module stream.
+    let mut file = cfb::CompoundFile::open(Cursor::new(data)).unwrap();
+    let mut stream = compressed.clone();
+    stream.extend_from_slice(b"\xff\xff\xff");
+    file.create_stream("/VBA/Module1")
+        .unwrap()
+        .write_all(&stream)
+        .unwrap();
+    let data = file.into_inner().into_inner();
+    let project = open_project(data.clone()).unwrap();
+    assert!(project.module_compressed_source("Module1").is_err());
+    let project = ProjectOptions::from(ParseOptions { lenient: true })
+        .open(data)
+        .unwrap();
+    assert_eq!(
+        project.module_compressed_source("Module1").unwrap(),
+        compressed
+    );
+}
+
+#[test]
+fn module_reserved_fields() {
+    use super::{parse_dir_stream, Error, ModuleType, ParseOptions};
+
+    // A MODULE Record, with `flags` (MODULETYPE, MODULEREADONLY, MODULEPRIVATE, and the
+    // Terminator) given verbatim.
+    let dir_stream = |flags: &[u8]| {
+        let mut records = dir_stream_records();
+        records[11] = [
+            record(0x000f, &1_u16.to_le_bytes()),
+            record(0x0013, &0xffff_u16.to_le_bytes()),
+            record(0x0019, b"Module1"),
+            record(0x001a, b"Module1"),
+            record(0x0032, b"M\0o\0d\0u\0l\0e\x001\0"),
+            record(0x001c, b""),
+            record(0x0048, b""),
+            record(0x0031, &0_u32.to_le_bytes()),
+            record(0x001e, &0_u32.to_le_bytes()),
+            record(0x002c, &0xffff_u16.to_le_bytes()),
+            flags.to_vec(),
+        ]
+        .concat();
+        records.concat()
+    };
+    let context = |flags: &[u8]| match parse_dir_stream(&dir_stream(flags), ParseOptions::default())
+    {
+        Err(Error::Parser { context, .. }) => context,
+        other => panic!("expected a Parser error, found {:?}", other),
+    };
+
+    let information = parse_dir_stream(
+        &dir_stream(b"\x22\0\0\0\0\0\x25\0\0\0\0\0\x28\0\0\0\0\0\x2b\0\0\0\0\0"),
+        ParseOptions::default(),
+    )
+    .unwrap();
+    let module = &information.modules[0];
+    assert_eq!(module.module_type, ModuleType::DocClsDesigner);
+    assert!(module.read_only);
+    assert!(module.private);
+
+    assert_eq!(context(b"\x23\0\0\0\0\0\x2b\0\0\0\0\0"), "MODULETYPE");
+    assert_eq!(
+        context(b"\x21\0\x01\0\0\0\x2b\0\0\0\0\0"),
+        "MODULETYPE Reserved"
+    );
+    assert_eq!(
+        context(b"\x21\0\0\0\0\0\x25\0\x01\0\0\0\x2b\0\0\0\0\0"),
+        "MODULEREADONLY Reserved"
+    );
+    assert_eq!(
+        context(b"\x21\0\0\0\0\0\x28\0\0\0\0\x01\x2b\0\0\0\0\0"),
+        "MODULEPRIVATE Reserved"
+    );
+    assert_eq!(
+        context(b"\x21\0\0\0\0\0\x2b\0\0\0\x01\0"),
+        "MODULE Terminator Reserved"
+    );
+}
+
+#[test]
+fn list_entry_kinds() {
+    use super::{open_project, EntryKind};
+
+    let project = open_project(vba_project(&[("Module1", "")])).unwrap();
+    let entries = project.list_entries().unwrap();
+    let kinds = entries
+        .iter()
+        .map(|entry| (entry.path.as_str(), entry.kind))
+        .collect::<Vec<_>>();
+    assert!(kinds.contains(&("/", EntryKind::Storage)));
+    assert!(kinds.contains(&("/VBA", EntryKind::Storage)));
+    assert!(kinds.contains(&("/VBA/dir", EntryKind::Stream)));
+    assert!(kinds.contains(&("/VBA/Module1", EntryKind::Stream)));
+    assert!(kinds.contains(&("/PROJECT", EntryKind::Stream)));
+
+    let names = entries
+        .into_iter()
+        .map(|entry| (entry.name, entry.path))
+        .collect::<Vec<_>>();
+    assert_eq!(names, project.list().unwrap());
+}
+
+#[test]
+fn entry_tree() {
+    use super::open_project;
+
+    let project = open_project(vba_project_at("/Macros", &[("Module1", "")])).unwrap();
+    let tree = project.tree().unwrap();
+    assert_eq!(tree.path, "/");
+    assert_eq!(tree.name, "Root Entry");
+    assert!(tree.streams.is_empty());
+    assert_eq!(tree.storages.len(), 1);
+
+    let macros = &tree.storages[0];
+    assert_eq!(
+        (macros.name.as_str(), macros.path.as_str()),
+        ("Macros", "/Macros")
+    );
+    let streams = macros
+        .streams
+        .iter()
+        .map(|entry| entry.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(streams, ["PROJECT"]);
+    assert_eq!(macros.storages.len(), 1);
+
+    let vba = &macros.storages[0];
+    assert_eq!(vba.path, "/Macros/VBA");
+    assert!(vba.storages.is_empty());
+    let mut streams = vba
+        .streams
+        .iter()
+        .map(|entry| entry.path.as_str())
+        .collect::<Vec<_>>();
+    streams.sort_unstable();
+    assert_eq!(
+        streams,
+        [
+            "/Macros/VBA/Module1",
+            "/Macros/VBA/_VBA_PROJECT",
+            "/Macros/VBA/dir"
+        ]
+    );
+}
+
+#[test]
+fn unicode_variants() {
+    use super::{parse_dir_stream, ParseOptions};
+
+    let mut records = dir_stream_records();
+    // "Ω" cannot be represented in Windows-1252.
+    records[5] = [
+        record(0x0005, b"Omega ?"),
+        record(0x0040, &utf16("Omega Ω")),
+    ]
+    .concat();
+    records[10] = [
+        record(0x000c, b"Omega = 1"),
+        record(0x003c, &utf16("Omega = 1")),
+    ]
+    .concat();
+    records[11] = [
+        record(0x000f, &1_u16.to_le_bytes()),
+        record(0x0013, &0xffff_u16.to_le_bytes()),
+        record(0x0019, b"Modul?"),
+        record(0x0047, &utf16("ModulΩ")),
+        record(0x001a, b"Modul?"),
+        record(0x0032, &utf16("ModulΩ")),
+        record(0x001c, b"?"),
+        record(0x0048, &utf16("Ω")),
+        record(0x0031, &0_u32.to_le_bytes()),
+        record(0x001e, &0_u32.to_le_bytes()),
+        record(0x002c, &0xffff_u16.to_le_bytes()),
+        record(0x0021, b""),
+        record(0x002b, b""),
+    ]
+    .concat();
+    let information = parse_dir_stream(&records.concat(), ParseOptions::default()).unwrap();
+
+    let project = &information.information;
+    assert_eq!(project.doc_string(), "Omega ?");
+    assert_eq!(project.doc_string_best(), "Omega Ω");
+    assert_eq!(project.constants_best(), "Omega = 1");
+    let module = &information.modules[0];
+    assert_eq!(module.name, "Modul?");
+    assert_eq!(module.name_best(), "ModulΩ");
+    assert_eq!(module.stream_name_best(), "ModulΩ");
+    assert_eq!(module.doc_string_best(), "Ω");
+
+    // Empty Unicode variants fall back to the MBCS text.
+    let mut records = dir_stream_records();
+    records[5] = [record(0x0005, b"Description"), record(0x0040, b"")].concat();
+    let information = parse_dir_stream(&records.concat(), ParseOptions::default()).unwrap();
+    assert_eq!(information.information.doc_string_best(), "Description");
+    assert_eq!(information.information.constants_best(), "");
+}
+
+#[test]
+fn library_flags() {
+    use super::{parse_dir_stream, LibFlags, ParseOptions};
+
+    let records = dir_stream_records();
+    let information = parse_dir_stream(&records.concat(), ParseOptions::default()).unwrap();
+    assert!(information.information.library_flags().is_empty());
+
+    // PROJECTLIBFLAGS with CONTROL, HIDDEN, and an undefined bit set
+    let mut records = dir_stream_records();
+    records[8] = b"\x08\x00\x04\x00\x00\x00\x06\x00\x01\x00".to_vec();
+    let information = parse_dir_stream(&records.concat(), ParseOptions::default()).unwrap();
+    let flags = information.information.library_flags();
+    assert_eq!(flags, LibFlags::CONTROL | LibFlags::HIDDEN);
+    assert!(!flags.contains(LibFlags::RESTRICTED));
+    assert_eq!(information.information.lib_flags(), 0x0001_0006);
+}
+
+#[test]
+fn entry_times() {
+    use super::{open_project, Error};
+    use std::time::{Duration, SystemTime};
+
+    let before = SystemTime::now() - Duration::from_secs(1);
+    let project = open_project(vba_project(&[("Module1", "")])).unwrap();
+
+    // The cfb crate timestamps storages on creation, but not the root storage.
+    let (created, modified) = project.entry_times("/VBA").unwrap();
+    assert!(created.unwrap() >= before);
+    assert!(modified.unwrap() >= before);
+    let (created, _) = project.entry_times("/").unwrap();
+    assert_eq!(created, None);
+
+    match project.entry_times("VBA\\missing") {
+        Err(Error::MissingStream { path }) => assert_eq!(path, "/VBA/missing"),
+        _ => panic!("expected a MissingStream error"),
+    }
+}
+
+#[test]
+fn zero_length_dir_stream_strings() {
+    use super::{parse_dir_stream, ParseOptions};
+
+    // Zero-length MBCS and Unicode variants, followed by records with non-zero values
+    // that would be misread if any of the empty records consumed neighboring bytes.
+    let mut records = dir_stream_records();
+    records[5] = [record(0x0005, b""), record(0x0040, b"")].concat();
+    records[6] = [record(0x0006, b""), record(0x003d, b"")].concat();
+    records[7] = record(0x0007, &0x1234_5678_u32.to_le_bytes());
+    records[10] = [record(0x000c, b""), record(0x003c, b"")].concat();
+    let information = parse_dir_stream(&records.concat(), ParseOptions::default()).unwrap();
+    let project = &information.information;
+    assert_eq!(project.doc_string(), "");
+    assert_eq!(project.doc_string_best(), "");
+    assert_eq!(project.help_file(), "");
+    assert_eq!(project.help_context(), 0x1234_5678);
+    assert_eq!(project.version(), (1, 2));
+    assert_eq!(project.constants(), "");
+
+    // A non-empty MBCS string with a zero-length Unicode variant
+    records[5] = [record(0x0005, b"Description"), record(0x0040, b"")].concat();
+    records[6] = [record(0x0006, b"help.chm"), record(0x003d, b"")].concat();
+    let information = parse_dir_stream(&records.concat(), ParseOptions::default()).unwrap();
+    let project = &information.information;
+    assert_eq!(project.doc_string(), "Description");
+    assert_eq!(project.doc_string_best(), "Description");
+    assert_eq!(project.help_file(), "help.chm");
+    assert_eq!(project.help_context(), 0x1234_5678);
+}
+
+#[test]
+fn project_raw_bytes() {
+    use super::open_project;
+    use std::io::Cursor;
+
+    let data = vba_project(&[("Module1", "Sub Main()\r\nEnd Sub\r\n")]);
+    let mut project = open_project(data.clone()).unwrap();
+    assert_eq!(project.raw_bytes(), &data[..]);
+
+    // Modifications are not reflected.
+    project
+        .set_module_source("Module1", "Sub Other()\r\nEnd Sub\r\n")
+        .unwrap();
+    assert_eq!(project.raw_bytes(), &data[..]);
+    let mut modified = Cursor::new(Vec::new());
+    project.save(&mut modified).unwrap();
+    assert_ne!(modified.get_ref(), &data);
+}
+
+#[test]
+fn export_module() {
+    use super::{open_project, ModuleKind, ModuleType};
+
+    let mut project = open_project(vba_project(&[(
+        "Module1",
+        "Attribute VB_Name = \"Module1\"\r\nSub Main()\r\nEnd Sub\r\n",
+    )]))
+    .unwrap();
+    project
+        .add_module(
+            "Class1",
+            "Attribute VB_Name = \"Class1\"\r\n",
+            ModuleType::DocClsDesigner,
+        )
+        .unwrap();
+    project
+        .add_module(
+            "UserForm1",
+            "Attribute VB_Name = \"UserForm1\"\r\n",
+            ModuleType::DocClsDesigner,
+        )
+        .unwrap();
+    // Turn UserForm1 into a designer module.
+    let properties = project.read_stream("/PROJECT").unwrap();
+    let properties = String::from_utf8(properties)
+        .unwrap()
+        .replace("Class=UserForm1", "BaseClass=UserForm1");
+    project
+        .write_stream("/PROJECT", properties.as_bytes())
+        .unwrap();
+
+    assert_eq!(
+        project.export_module("Module1").unwrap(),
+        project.module_source("Module1").unwrap()
+    );
+    assert_eq!(
+        project.export_module("Class1").unwrap(),
+        "VERSION 1.0 CLASS\r\nBEGIN\r\n  MultiUse = -1  'True\r\nEND\r\n\
+         Attribute VB_Name = \"Class1\"\r\n"
+    );
+    assert_eq!(
+        project.export_module("UserForm1").unwrap(),
+        "VERSION 5.00\r\nBegin {C62A69F0-16DC-11CE-9E98-00AA00574A4F} UserForm1 \r\nEnd\r\n\
+         Attribute VB_Name = \"UserForm1\"\r\n"
+    );
+    assert!(project.export_module("Module2").is_err());
+
+    let extensions = ["Module1", "Class1", "UserForm1"]
+        .iter()
+        .map(|name| project.module_kind(name).unwrap().file_extension())
+        .collect::<Vec<_>>();
+    assert_eq!(extensions, ["bas", "cls", "frm"]);
+    assert_eq!(ModuleKind::Document.file_extension(), "cls");
+}
+
+/// Encrypts `data` using the Data Encryption algorithm (MS-OVBA 2.4.3.2), returning the
+/// hexadecimal text representation. The ignored bytes are filled with `0xAA`.
+fn encrypt_data(seed: u8, project_key: u8, data: &[u8]) -> String {
+    let version_enc = seed ^ 2;
+    let project_key_enc = seed ^ project_key;
+    let mut result = vec![seed, version_enc, project_key_enc];
+
+    let mut unencrypted_byte_1 = project_key;
+    let mut encrypted_byte_1 = project_key_enc;
+    let mut encrypted_byte_2 = version_enc;
+    let ignored = vec![0xaa; usize::from((seed & 6) / 2)];
+    let len = (data.len() as u32).to_le_bytes();
+    for &byte in ignored.iter().chain(&len).chain(data) {
+        let byte_enc = byte ^ encrypted_byte_2.wrapping_add(unencrypted_byte_1);
+        result.push(byte_enc);
+        encrypted_byte_2 = encrypted_byte_1;
+        encrypted_byte_1 = byte_enc;
+        unencrypted_byte_1 = byte;
+    }
+
+    result.iter().map(|byte| format!("{:02X}", byte)).collect()
+}
+
+#[test]
+fn data_decryption() {
+    use super::{parser::decrypt_data, Error};
+
+    for &seed in &[0x00, 0x02, 0x04, 0x06, 0xff] {
+        for data in &[
+            &b""[..],
+            b"\x00",
+            b"\xff",
+            b"\x01\x02\x03\x04\x05\x06\x07\x08",
+        ] {
+            let encrypted = encrypt_data(seed, 0x3c, data);
+            assert_eq!(decrypt_data(&encrypted).unwrap(), *data);
+        }
+    }
+
+    let context = |hex: &str| match decrypt_data(hex) {
+        Err(Error::Parser { context, .. }) => context,
+        _ => panic!("expected a Parser error"),
+    };
+    assert_eq!(context("0"), "Encrypted data hex digits");
+    assert_eq!(context("0G"), "Encrypted data hex digits");
+    assert_eq!(context("0002"), "Encrypted data truncated");
+    assert_eq!(context("000000"), "Encrypted data version");
+    let encrypted = encrypt_data(0, 0x3c, b"\x01\x02");
+    assert_eq!(
+        context(&encrypted[..encrypted.len() - 2]),
+        "Encrypted data length"
+    );
+}
+
+#[test]
+fn locked_projects() {
+    use super::open_project;
+
+    let mut project = open_project(vba_project(&[("Module1", "Sub Main()\r\n")])).unwrap();
+    assert!(!project.is_locked().unwrap());
+
+    let properties = String::from_utf8(project.read_stream("/PROJECT").unwrap()).unwrap();
+    for &(visibility_state, locked) in &[(0x00, true), (0xff, false)] {
+        let gc = encrypt_data(0x12, 0x3c, &[visibility_state]);
+        let text = format!("{}GC=\"{}\"\r\n", properties, gc);
+        project.write_stream("/PROJECT", text.as_bytes()).unwrap();
+        assert_eq!(project.is_locked().unwrap(), locked);
+        // The source code remains accessible.
+        assert_eq!(project.module_source("Module1").unwrap(), "Sub Main()\r\n");
+    }
+
+    let text = format!("{}GC=\"XYZ\"\r\n", properties);
+    project.write_stream("/PROJECT", text.as_bytes()).unwrap();
+    assert!(project.is_locked().is_err());
+}
+
+#[test]
+fn protection() {
+    use super::{open_project, Protection};
+
+    let mut project = open_project(vba_project(&[("Module1", "Sub Main()\r\n")])).unwrap();
+    assert_eq!(project.protection().unwrap(), Protection::default());
+
+    let properties = String::from_utf8(project.read_stream("/PROJECT").unwrap()).unwrap();
+    let password = [0xff, 0x08, 0x11, 0x22, 0x33, 0x01, 0x02, 0x03];
+    let text = format!(
+        "{}CMG=\"{}\"\r\nDPB=\"{}\"\r\nGC=\"{}\"\r\n",
+        properties,
+        encrypt_data(0x40, 0x3c, &[0x01, 0x00, 0x00, 0x00]),
+        encrypt_data(0x7f, 0x3c, &password),
+        encrypt_data(0x12, 0x3c, &[0x00]),
+    );
+    project.write_stream("/PROJECT", text.as_bytes()).unwrap();
+    assert_eq!(
+        project.protection().unwrap(),
+        Protection {
+            protection_state: Some(vec![0x01, 0x00, 0x00, 0x00]),
+            password: Some(password.to_vec()),
+            visibility_state: Some(vec![0x00]),
+        }
+    );
+}
+
+#[test]
+fn project_summary() {
+    use super::{open_project, ProjectSummary};
+
+    let mut project = open_project(vba_project(&[("Module1", ""), ("Module2", "")])).unwrap();
+    let summary = ProjectSummary {
+        name: project.information.name().to_owned(),
+        sys_kind: project.information.sys_kind,
+        code_page: project.information.code_page,
+        module_count: 2,
+        reference_count: 0,
+        has_signature: false,
+        is_locked: Some(false),
+        vba_version: Some(0xffff),
+    };
+    assert_eq!(project.summary().unwrap(), summary);
+
+    let properties = String::from_utf8(project.read_stream("/PROJECT").unwrap()).unwrap();
+    let gc = encrypt_data(0x12, 0x3c, &[0x00]);
+    let text = format!("{}GC=\"{}\"\r\n", properties, gc);
+    project.write_stream("/PROJECT", text.as_bytes()).unwrap();
+    project
+        .write_stream("/\x05DigitalSignature", &[0x00; 4])
+        .unwrap();
+    project
+        .container
+        .get_mut()
+        .remove_stream("/VBA/_VBA_PROJECT")
+        .unwrap();
+    assert_eq!(
+        project.summary().unwrap(),
+        ProjectSummary {
+            has_signature: true,
+            is_locked: Some(true),
+            vba_version: None,
+            ..summary
+        }
+    );
+
+    // A missing *PROJECT* stream doesn't fail the summary.
+    project
+        .container
+        .get_mut()
+        .remove_stream("/PROJECT")
+        .unwrap();
+    assert_eq!(project.summary().unwrap().is_locked, None);
+}
+
+#[test]
+fn decompress_stream_from_wrong_offset() {
+    use super::{open_project, Error};
+
+    let project = open_project(vba_project(&[("Module1", "Sub Main()\r\n")])).unwrap();
+    assert_eq!(
+        project.decompress_stream_from("/VBA/Module1", 0).unwrap(),
+        b"Sub Main()\r\n"
+    );
+    // Points into the CompressedChunkHeader.
+    match project.decompress_stream_from("/VBA/Module1", 2) {
+        Err(Error::Decompressor { offset, reason }) => {
+            assert_eq!(offset, 0);
+            assert_eq!(
+                reason,
+                "Invalid CompressedContainer SignatureByte (expected 0x01)"
+            );
+        }
+        _ => panic!("expected a decompressor error"),
+    }
+}
+
+#[test]
+fn embedded_objects() {
+    use super::open_project;
+    use std::io::{Cursor, Write};
+
+    let data = vba_project_at("/Macros", &[("Module1", "")]);
+    let mut file = cfb::CompoundFile::open(Cursor::new(data)).unwrap();
+    file.create_storage("/ObjectPool").unwrap();
+    file.create_storage("/ObjectPool/_1").unwrap();
+    let mut write = |path: &str, data: &[u8]| {
+        file.create_stream(path).unwrap().write_all(data).unwrap();
+    };
+    write("/\x01Ole", b"document");
+    write("/Macros/VBA/CONTENTS", b"not an object");
+    write("/ObjectPool/_1/\x01Ole", b"\x01\x00\x00\x02");
+    write("/ObjectPool/_1/\x01CompObj", b"");
+    write("/ObjectPool/_1/CONTENTS", b"object");
+
+    let project = open_project(file.into_inner().into_inner()).unwrap();
+    assert_eq!(project.project_root(), "/Macros");
+    assert_eq!(
+        project.embedded_objects().unwrap(),
+        vec![
+            (
+                "/ObjectPool/_1/\x01Ole".to_owned(),
+                b"\x01\x00\x00\x02".to_vec()
+            ),
+            ("/ObjectPool/_1/CONTENTS".to_owned(), b"object".to_vec()),
+        ]
+    );
+}
+
+#[test]
+fn project_manifest() {
+    use super::{open_project, HashAlgorithm, ModuleKind, ModuleManifest, SignatureKind};
+
+    let mut project = open_project(vba_project(&[("Module1", "Sub Main()\r\n")])).unwrap();
+    let manifest = project.manifest().unwrap();
+    assert_eq!(manifest.information, project.information);
+    assert_eq!(manifest.references, project.references);
+    assert_eq!(
+        manifest.modules,
+        vec![ModuleManifest {
+            name: "Module1".to_owned(),
+            kind: Some(ModuleKind::Procedural),
+            stream_path: "/VBA/Module1".to_owned(),
+            source_hash: Some(
+                project
+                    .module_source_hash("Module1", HashAlgorithm::Sha256)
+                    .unwrap()
+            ),
+            stream_size: Some(project.module_stream_size("Module1").unwrap()),
+        }]
+    );
+    assert_eq!(manifest.signature, None);
+    assert_eq!(manifest.is_locked, Some(false));
+
+    project
+        .write_stream("/\x05DigitalSignatureAgile", &[0x00; 4])
+        .unwrap();
+    assert_eq!(
+        project.manifest().unwrap().signature,
+        Some(SignatureKind::Agile)
+    );
+
+    // Missing streams are reported as unknown details. The kind of procedural modules
+    // is known from the *dir* stream.
+    let container = project.container.get_mut();
+    container.remove_stream("/PROJECT").unwrap();
+    container.remove_stream("/VBA/Module1").unwrap();
+    let manifest = project.manifest().unwrap();
+    assert_eq!(manifest.is_locked, None);
+    assert_eq!(
+        manifest.modules,
+        vec![ModuleManifest {
+            name: "Module1".to_owned(),
+            kind: Some(ModuleKind::Procedural),
+            stream_path: "/VBA/Module1".to_owned(),
+            source_hash: None,
+            stream_size: None,
+        }]
+    );
+}
+
+#[test]
+fn dbcs_chunk_boundary() {
+    use super::{open_project, string_to_cp};
+
+    // A CompressedChunk decompresses to at most 4096 bytes. Place a double-byte
+    // character such that its lead byte ends the first chunk, and its trail byte starts
+    // the second.
+    let mut text = "Attribute VB_Name = \"Module1\"\r\nSub Main()\r\n".to_owned();
+    while text.len() < 4000 {
+        text.push_str("    ' padding\r\n");
+    }
+    let declaration = "    Dim ";
+    let fill = 4095 - text.len() - "    '\r\n".len() - declaration.len();
+    text.push_str(&format!("    '{}\r\n{}", "x".repeat(fill), declaration));
+    assert_eq!(text.len(), 4095);
+    text.push_str("テスト As Long\r\nEnd Sub\r\n");
+    let data = string_to_cp(&text, 932).unwrap();
+    assert_eq!(&data[4095..4097], b"\x83\x65");
+
+    let mut project = open_project(vba_project(&[("Module1", "")])).unwrap();
+    project
+        .write_stream("/VBA/Module1", &compress(&data))
+        .unwrap();
+    project.information.code_page = 932;
+    assert_eq!(project.module_source("Module1").unwrap(), text);
+    assert!(project
+        .module_source_parts("Module1")
+        .unwrap()
+        .normalized_code()
+        .contains("Dim テスト As Long"));
+}
+
+#[test]
+fn open_borrowed_data() {
+    use super::open_project_from_reader;
+    use std::io::Cursor;
+
+    let data = vba_project(&[("Module1", "Sub Main()\r\n")]);
+    let project = open_project_from_reader(Cursor::new(&data[..])).unwrap();
+    assert_eq!(project.module_source("Module1").unwrap(), "Sub Main()\r\n");
+    drop(project);
+    // The data remains owned by the caller.
+    assert!(!data.is_empty());
+}
+
+#[test]
+fn potentially_stomped() {
+    use super::{open_project, ModuleType, Project};
+    use std::io::Cursor;
+
+    // Replaces a module's stream with a PerformanceCache of `cache_len` bytes, followed
+    // by the compressed `source`.
+    fn set_module_stream(
+        project: &mut Project<Cursor<Vec<u8>>>,
+        index: usize,
+        cache_len: usize,
+        source: &str,
+    ) {
+        let path = format!("/VBA/{}", project.modules[index].name);
+        let data = [vec![0xaa; cache_len], compress(source.as_bytes())].concat();
+        project.write_stream(&path, &data).unwrap();
+        project.modules[index].text_offset = cache_len;
+    }
+
+    let attributes = "Attribute VB_Name = \"ThisWorkbook\"\r\n";
+    let code = "Sub Main()\r\nEnd Sub\r\n";
+    let mut project = open_project(vba_project(&[
+        ("ThisWorkbook", attributes),
+        ("Module1", code),
+    ]))
+    .unwrap();
+    project
+        .write_stream("/VBA/_VBA_PROJECT", b"\xCC\x61\xB2\x00\x00\x03\x00\xAA")
+        .unwrap();
+    let properties = String::from_utf8(project.read_stream("/PROJECT").unwrap()).unwrap();
+    let properties = properties.replace("Module=ThisWorkbook", "Document=ThisWorkbook/&H00000000");
+    project
+        .write_stream("/PROJECT", properties.as_bytes())
+        .unwrap();
+    project.modules[0].module_type = ModuleType::DocClsDesigner;
+    assert!(!project.is_potentially_stomped().unwrap());
+
+    // An empty document module with a small PerformanceCache, as commonly found in
+    // documents
+    set_module_stream(&mut project, 0, 1024, attributes);
+    set_module_stream(&mut project, 1, 1024, code);
+    assert!(!project.is_potentially_stomped().unwrap());
+
+    // A stomped document module
+    set_module_stream(&mut project, 0, 4096, attributes);
+    assert!(project.is_potentially_stomped().unwrap());
+    set_module_stream(&mut project, 0, 1024, attributes);
+
+    // A stomped procedural module
+    set_module_stream(&mut project, 1, 1024, "Attribute VB_Name = \"Module1\"\r\n");
+    assert!(project.is_potentially_stomped().unwrap());
+
+    // A module with a PerformanceCache and a corrupted CompressedContainer
+    set_module_stream(&mut project, 1, 1024, code);
+    assert!(!project.is_potentially_stomped().unwrap());
+    project
+        .write_stream("/VBA/Module1", &[vec![0xaa; 1024], vec![0x02; 16]].concat())
+        .unwrap();
+    assert!(project.is_potentially_stomped().unwrap());
+
+    // Modules that cannot be classified are checked as non-document modules.
+    set_module_stream(&mut project, 0, 0, attributes);
+    set_module_stream(&mut project, 1, 1024, code);
+    project
+        .container
+        .get_mut()
+        .remove_stream("/PROJECT")
+        .unwrap();
+    assert!(!project.is_potentially_stomped().unwrap());
+    set_module_stream(&mut project, 0, 1024, attributes);
+    assert!(project.is_potentially_stomped().unwrap());
+
+    // The *_VBA_PROJECT* stream's version denies the existence of a PerformanceCache.
+    let mut project = open_project(vba_project(&[("Module1", code)])).unwrap();
+    assert!(!project.is_potentially_stomped().unwrap());
+    set_module_stream(&mut project, 0, 1024, code);
+    assert!(project.is_potentially_stomped().unwrap());
+}
+
+#[test]
+fn message_digests() {
+    use super::{hash::digest, HashAlgorithm};
+
+    fn hex(data: &[u8]) -> String {
+        data.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    // Test vectors from RFC 1321 and FIPS 180-4
+    let two_blocks = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+    let cases: &[(HashAlgorithm, &[u8], &str)] = &[
+        (HashAlgorithm::Md5, b"", "d41d8cd98f00b204e9800998ecf8427e"),
+        (
+            HashAlgorithm::Md5,
+            b"abc",
+            "900150983cd24fb0d6963f7d28e17f72",
+        ),
+        (
+            HashAlgorithm::Md5,
+            b"12345678901234567890123456789012345678901234567890123456789012345678901234567890",
+            "57edf4a22be3c955ac49da2e2107b67a",
+        ),
+        (
+            HashAlgorithm::Sha1,
+            b"abc",
+            "a9993e364706816aba3e25717850c26c9cd0d89d",
+        ),
+        (
+            HashAlgorithm::Sha1,
+            two_blocks,
+            "84983e441c3bd26ebaae4aa1f95129e5e54670f1",
+        ),
+        (
+            HashAlgorithm::Sha256,
+            b"",
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+        ),
+        (
+            HashAlgorithm::Sha256,
+            b"abc",
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+        ),
+        (
+            HashAlgorithm::Sha256,
+            two_blocks,
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+        ),
+    ];
+    for (algorithm, data, expected) in cases {
+        assert_eq!(hex(&digest(*algorithm, data)), *expected, "{:?}", algorithm);
+    }
+}
+
+#[test]
+fn v3_content_normalized_data() {
+    use super::{open_project, writer::write_v3_content_normalized_data};
+
+    let project = open_project(vba_project(&[
+        (
+            "Module1",
+            "Attribute VB_Name = \"Module1\"\r\nAttribute VB_Exposed = False\rSub A()\nEnd Sub\r\n",
+        ),
+        (
+            "Module2",
+            "Attribute VB_Name = \"Module2\"\r\nAttribute VB_Exposed = True\r\n",
+        ),
+    ]))
+    .unwrap();
+
+    let module = |name: &str| {
+        [
+            record(0x0019, name.as_bytes()),
+            record(0x0047, &utf16(name)),
+            record(0x001a, name.as_bytes()),
+            record(0x0032, &utf16(name)),
+            record(0x001c, b""),
+            record(0x0048, b""),
+            // MODULEOFFSET, MODULEHELPCONTEXT, MODULECOOKIE, and MODULETYPE
+            b"\x31\x00\x04\x00\x00\x00".to_vec(),
+            record(0x001e, &0_u32.to_le_bytes()),
+            b"\x2c\x00\x02\x00\x00\x00".to_vec(),
+            b"\x21\x00\x00\x00\x00\x00".to_vec(),
+        ]
+        .concat()
+    };
+    let expected = [
+        // PROJECTSYSKIND without its value, followed by the records up to PROJECTCONSTANTS
+        b"\x01\x00\x04\x00\x00\x00".to_vec(),
+        dir_stream_records()[1..11].concat(),
+        // PROJECTMODULES and PROJECTCOOKIE without their values
+        b"\x0f\x00\x02\x00\x00\x00\x13\x00\x02\x00\x00\x00".to_vec(),
+        module("Module1"),
+        b"Attribute VB_Exposed = False\nSub A()\nEnd Sub\nModule1\n".to_vec(),
+        // Only default attributes; the module name isn't appended either.
+        module("Module2"),
+    ]
+    .concat();
+    assert_eq!(project.v3_content_normalized_data().unwrap(), expected);
+
+    // The records are used as stored: A Unicode variant that differs from the MBCS text
+    // is kept, and the (optional) PROJECTCONSTANTS Record is absent.
+    let mut records = dir_stream_records();
+    let doc_string = [
+        record(0x0005, b"? Project"),
+        record(0x0040, &utf16("\u{3a9} Project")),
+    ]
+    .concat();
+    records[5] = doc_string.clone();
+    records.remove(10);
+    let (_, records) = parse_dir_records(&records.concat()).unwrap();
+    let expected = [
+        b"\x01\x00\x04\x00\x00\x00".to_vec(),
+        dir_stream_records()[1..5].concat(),
+        doc_string,
+        dir_stream_records()[6..10].concat(),
+        b"\x0f\x00\x02\x00\x00\x00\x13\x00\x02\x00\x00\x00".to_vec(),
+    ]
+    .concat();
+    assert_eq!(write_v3_content_normalized_data(&records, &[]), expected);
+}
+
+#[test]
+fn content_hashes() {
+    use super::{hash::digest, open_project, HashAlgorithm, SignatureKind};
+
+    let mut project = open_project(vba_project(&[("Module1", "Sub A()\r\nEnd Sub\r\n")])).unwrap();
+    let container = project.container.get_mut();
+    container.create_storage("/UserForm1").unwrap();
+    container.create_storage("/UserForm1/i07").unwrap();
+    project.write_stream("/UserForm1/f", b"abc").unwrap();
+    project.write_stream("/UserForm1/o", &[0xaa; 1024]).unwrap();
+    project.write_stream("/UserForm1/i07/f", b"d").unwrap();
+    let properties = "ID=\"{00000000-0000-0000-0000-000000000000}\"\r\n\
+        Module=Module1\r\n\
+        BaseClass=UserForm1\r\n\
+        Name=\"VBAProject\"\r\n\
+        CMG=\"0705D8E3D8EDDBF1DBF1DBF1DBF1\"\r\n\
+        \r\n\
+        [Host Extender Info]\r\n\
+        &H00000001={3832D640-CF90-11CF-8E43-00A0C911005A};VBE;&H00000000\r\n\
+        \r\n\
+        [Workspace]\r\n\
+        Module1=0, 0, 0, 0, C\r\n";
+    project
+        .write_stream("/PROJECT", properties.as_bytes())
+        .unwrap();
+
+    // Streams are padded to a multiple of 1023 bytes, nested storages included.
+    let forms = [
+        b"abc".to_vec(),
+        vec![0x00; 1020],
+        vec![0xaa; 1024],
+        vec![0x00; 1022],
+        b"d".to_vec(),
+        vec![0x00; 1022],
+    ]
+    .concat();
+    assert_eq!(project.forms_normalized_data().unwrap(), forms);
+
+    let expected = [
+        b"ModuleModule1".to_vec(),
+        forms.clone(),
+        b"BaseClassUserForm1".to_vec(),
+        b"Name\"VBAProject\"".to_vec(),
+        b"Host Extender Info".to_vec(),
+        b"&H00000001={3832D640-CF90-11CF-8E43-00A0C911005A};VBE;&H00000000".to_vec(),
+    ]
+    .concat();
+    let project_data = project.project_normalized_data().unwrap();
+    assert_eq!(project_data, expected);
+
+    let content = project.content_normalized_data().unwrap();
+    assert_eq!(
+        project
+            .content_hash(SignatureKind::Legacy, HashAlgorithm::Md5)
+            .unwrap(),
+        digest(HashAlgorithm::Md5, &content)
+    );
+    assert_eq!(
+        project
+            .content_hash(SignatureKind::Agile, HashAlgorithm::Sha256)
+            .unwrap(),
+        digest(HashAlgorithm::Sha256, &[content, forms].concat())
+    );
+    let v3_content = project.v3_content_normalized_data().unwrap();
+    assert_eq!(
+        project
+            .content_hash(SignatureKind::V3, HashAlgorithm::Sha1)
+            .unwrap(),
+        digest(HashAlgorithm::Sha1, &[v3_content, project_data].concat())
+    );
+}
@@ -0,0 +1,504 @@
+#![forbid(unsafe_code)]
+
+use crate::{
+    parser::{string_to_cp, string_to_cp_lossy},
+    Error, Information, Module, ModuleType, Reference, Result, SysKind,
+};
+
+/// Serializes *dir* stream records.
+///
+/// All text is encoded using the project's code page. Unicode variants of records are
+/// written from the Unicode variants as read from the *dir* stream, as these can hold
+/// characters the code page cannot represent.
+struct DirStreamWriter {
+    code_page: u16,
+    data: Vec<u8>,
+}
+
+impl DirStreamWriter {
+    fn u16(&mut self, value: u16) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn bytes(&mut self, value: &[u8]) {
+        self.u32(value.len() as u32);
+        self.data.extend_from_slice(value);
+    }
+
+    fn mbcs(&mut self, text: &str) -> Result<()> {
+        let code_page = self.code_page;
+        let data = string_to_cp(text, code_page).ok_or(Error::Encoding { code_page })?;
+        self.bytes(&data);
+        Ok(())
+    }
+
+    fn unicode(&mut self, text: &str) {
+        let data = text
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect::<Vec<_>>();
+        self.bytes(&data);
+    }
+
+    /// Writes the Unicode variant of `text`.
+    ///
+    /// This is `unicode`, as read from the *dir* stream, unless it is empty or no longer
+    /// matches `text` (e.g. after a module was renamed), in which case `text` is
+    /// written. `unicode` matches if it encodes to the same bytes as `text`, with
+    /// characters the code page cannot represent replaced by `?`.
+    fn unicode_variant(&mut self, text: &str, unicode: &str) {
+        let encoded = string_to_cp_lossy(unicode, self.code_page);
+        if !unicode.is_empty() && encoded == string_to_cp(text, self.code_page) {
+            self.unicode(unicode);
+        } else {
+            self.unicode(text);
+        }
+    }
+
+    /// Writes a record with a 32-bit `value`.
+    fn record_u32(&mut self, id: u16, value: u32) {
+        self.u16(id);
+        self.u32(4);
+        self.u32(value);
+    }
+
+    /// Writes a record with a 16-bit `value`.
+    fn record_u16(&mut self, id: u16, value: u16) {
+        self.u16(id);
+        self.u32(2);
+        self.u16(value);
+    }
+
+    /// Writes a record holding `text` in MBCS, followed by its Unicode variant; see
+    /// [`DirStreamWriter::unicode_variant`].
+    fn record_text(&mut self, id: u16, unicode_id: u16, text: &str, unicode: &str) -> Result<()> {
+        self.u16(id);
+        self.mbcs(text)?;
+        self.u16(unicode_id);
+        self.unicode_variant(text, unicode);
+        Ok(())
+    }
+
+    /// Writes a record whose size is computed from the data written by `f`.
+    fn record_sized<F>(&mut self, id: u16, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut Self) -> Result<()>,
+    {
+        self.u16(id);
+        let size_index = self.data.len();
+        self.u32(0);
+        f(self)?;
+        let size = (self.data.len() - size_index - 4) as u32;
+        self.data[size_index..size_index + 4].copy_from_slice(&size.to_le_bytes());
+        Ok(())
+    }
+
+    fn information(&mut self, information: &Information) -> Result<()> {
+        let sys_kind = match information.sys_kind {
+            SysKind::Win16 => 0x0000_0000,
+            SysKind::Win32 => 0x0000_0001,
+            SysKind::MacOs => 0x0000_0002,
+            SysKind::Win64 => 0x0000_0003,
+        };
+        self.record_u32(0x0001, sys_kind);
+        if let Some(compat_version) = information.compat_version {
+            self.record_u32(0x004a, compat_version);
+        }
+        self.record_u32(0x0002, information.lcid);
+        self.record_u32(0x0014, information.lcid_invoke);
+        self.record_u16(0x0003, information.code_page);
+
+        self.u16(0x0004);
+        self.mbcs(&information.name)?;
+
+        self.record_text(
+            0x0005,
+            0x0040,
+            &information.doc_string,
+            &information.doc_string_unicode,
+        )?;
+
+        // HelpFile1 and HelpFile2 MUST contain the same bytes.
+        self.u16(0x0006);
+        self.mbcs(&information.help_file_1)?;
+        self.u16(0x003d);
+        self.mbcs(&information.help_file_1)?;
+
+        self.record_u32(0x0007, information.help_context);
+        self.record_u32(0x0008, information.lib_flags);
+
+        // PROJECTVERSION: The Reserved field is fixed at 4, even though the record holds 6
+        // bytes of data.
+        self.u16(0x0009);
+        self.u32(4);
+        self.u32(information.version_major);
+        self.u16(information.version_minor);
+
+        self.record_text(
+            0x000c,
+            0x003c,
+            &information.constants,
+            &information.constants_unicode,
+        )
+    }
+
+    fn reference_name(&mut self, name: &Option<String>) -> Result<()> {
+        if let Some(name) = name {
+            self.record_text(0x0016, 0x003e, name, name)?;
+        }
+        Ok(())
+    }
+
+    fn reference(&mut self, reference: &Reference) -> Result<()> {
+        match reference {
+            Reference::Control(value) => {
+                // The parser reports a REFERENCEORIGINAL Record preceding the
+                // REFERENCECONTROL Record as a separate `Reference::Original`, which is
+                // written in its place. Writing `libid_original` here as well would
+                // duplicate it.
+                self.reference_name(&value.name)?;
+                self.record_sized(0x002f, |w| {
+                    w.mbcs(&value.libid_twiddled)?;
+                    w.u32(0);
+                    w.u16(0);
+                    Ok(())
+                })?;
+                self.reference_name(&value.name_extended)?;
+                self.record_sized(0x0030, |w| {
+                    w.mbcs(&value.libid_extended)?;
+                    w.u32(0);
+                    w.u16(0);
+                    w.data.extend_from_slice(&value.guid);
+                    w.u32(value.cookie);
+                    Ok(())
+                })
+            }
+            Reference::Original(value) => {
+                self.reference_name(&value.name)?;
+                self.u16(0x0033);
+                self.mbcs(&value.libid_original)
+            }
+            Reference::Registered(value) => {
+                self.reference_name(&value.name)?;
+                self.record_sized(0x000d, |w| {
+                    w.mbcs(&value.libid)?;
+                    w.u32(0);
+                    w.u16(0);
+                    Ok(())
+                })
+            }
+            Reference::Project(value) => {
+                self.reference_name(&value.name)?;
+                self.record_sized(0x000e, |w| {
+                    w.mbcs(&value.libid_absolute)?;
+                    w.mbcs(&value.libid_relative)?;
+                    w.u32(value.major_version);
+                    w.u16(value.minor_version);
+                    Ok(())
+                })
+            }
+        }
+    }
+
+    fn module(&mut self, module: &Module) -> Result<()> {
+        self.u16(0x0019);
+        self.mbcs(&module.name)?;
+        self.u16(0x0047);
+        self.unicode_variant(&module.name, &module.name_unicode);
+
+        self.u16(0x001a);
+        self.mbcs(&module.stream_name)?;
+        self.u16(0x0032);
+        self.unicode_variant(&module.stream_name, &module.stream_name_unicode);
+
+        self.record_text(
+            0x001c,
+            0x0048,
+            &module.doc_string,
+            &module.doc_string_unicode,
+        )?;
+        self.record_u32(0x0031, module.text_offset as u32);
+        self.record_u32(0x001e, module.help_context);
+        // Cookie MUST be 0xFFFF on write.
+        self.record_u16(0x002c, 0xffff);
+
+        let module_type = match module.module_type {
+            ModuleType::Procedural => 0x0021,
+            ModuleType::DocClsDesigner => 0x0022,
+        };
+        self.u16(module_type);
+        self.u32(0);
+        if module.read_only {
+            self.u16(0x0025);
+            self.u32(0);
+        }
+        if module.private {
+            self.u16(0x0028);
+            self.u32(0);
+        }
+
+        // Terminator
+        self.u16(0x002b);
+        self.u32(0);
+
+        Ok(())
+    }
+}
+
+/// Serializes a *dir* stream, the inverse of [`crate::parser::parse_project_information`].
+///
+/// The result is uncompressed. Cookies are written as 0xFFFF, as required by MS-OVBA.
+pub(crate) fn write_dir_stream(
+    information: &Information,
+    references: &[Reference],
+    modules: &[Module],
+) -> Result<Vec<u8>> {
+    let mut writer = DirStreamWriter {
+        code_page: information.code_page,
+        data: Vec::new(),
+    };
+
+    writer.information(information)?;
+    for reference in references {
+        writer.reference(reference)?;
+    }
+
+    writer.record_u16(0x000f, modules.len() as u16);
+    writer.record_u16(0x0013, 0xffff);
+    for module in modules {
+        writer.module(module)?;
+    }
+
+    // Terminator
+    writer.u16(0x0010);
+    writer.u32(0);
+
+    Ok(writer.data)
+}
+
+/// Splits source code into lines, as required by the content normalization.
+///
+/// Line terminators are CR, LF, or CRLF, and are not included. A final unterminated line
+/// is reported, unless it is empty.
+fn source_lines(source: &[u8]) -> Vec<&[u8]> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i < source.len() {
+        match source[i] {
+            b'\r' | b'\n' => {
+                lines.push(&source[start..i]);
+                if source[i] == b'\r' && source.get(i + 1) == Some(&b'\n') {
+                    i += 1;
+                }
+                start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    if start < source.len() {
+        lines.push(&source[start..]);
+    }
+    lines
+}
+
+/// Returns whether `line` starts with the `Attribute` keyword, ignoring ASCII case.
+fn is_attribute(line: &[u8]) -> bool {
+    const ATTRIBUTE: &[u8] = b"attribute";
+    line.len() >= ATTRIBUTE.len() && line[..ATTRIBUTE.len()].eq_ignore_ascii_case(ATTRIBUTE)
+}
+
+/// Produces the ContentNormalizedData of a VBA project, as specified in MS-OVBA
+/// section 2.4.2.1.
+///
+/// `records` holds the raw *dir* stream records, as returned by
+/// [`crate::parser::parse_dir_records`]. Text is taken from the records as stored, so the
+/// result doesn't depend on the code page used to decode the project. `sources` holds the
+/// decompressed source code of each module, in order of appearance in the *dir* stream.
+pub(crate) fn write_content_normalized_data(
+    records: &[(u16, Vec<u8>)],
+    sources: &[Vec<u8>],
+) -> Vec<u8> {
+    let mut data = Vec::new();
+    for (id, record) in records {
+        match id {
+            // PROJECTNAME and PROJECTCONSTANTS: ProjectName and Constants
+            0x0004 | 0x000c => data.extend_from_slice(record),
+            // REFERENCEREGISTERED: Id, followed by the record's data without its Size
+            0x000d => {
+                data.extend_from_slice(&id.to_le_bytes());
+                data.extend_from_slice(record);
+            }
+            // REFERENCEPROJECT: The data is copied up to (not including) the first 0x00
+            // byte, which is commonly part of SizeOfLibidAbsolute.
+            0x000e => {
+                let len = record
+                    .iter()
+                    .position(|&b| b == 0x00)
+                    .unwrap_or(record.len());
+                data.extend_from_slice(&record[..len]);
+            }
+            _ => {}
+        }
+    }
+
+    for source in sources {
+        // CR characters are dropped, and lines end at LF. Lines are appended without
+        // their terminators, skipping `Attribute` lines.
+        let source = source
+            .iter()
+            .filter(|&&b| b != b'\r')
+            .copied()
+            .collect::<Vec<_>>();
+        for line in source.split(|&b| b == b'\n') {
+            if !is_attribute(line) {
+                data.extend_from_slice(line);
+            }
+        }
+    }
+
+    data
+}
+
+/// Produces the V3ContentNormalizedData of a VBA project, as specified in MS-OVBA
+/// section 2.4.2.5.
+///
+/// This covers the raw *dir* stream `records` in order, save for values that change
+/// without a change to the project's content (such as the PROJECTSYSKIND value, offsets,
+/// and cookies). Each module's normalized source code follows its records. `sources`
+/// holds the decompressed source code of each module, in order of appearance in the
+/// *dir* stream.
+pub(crate) fn write_v3_content_normalized_data(
+    records: &[(u16, Vec<u8>)],
+    sources: &[Vec<u8>],
+) -> Vec<u8> {
+    // `Attribute` lines with these values are written for all modules of their kind,
+    // and don't contribute to the hash.
+    const DEFAULT_ATTRIBUTES: &[&[u8]] = &[
+        b"Attribute VB_Base = \"0{00020820-0000-0000-C000-000000000046}\"",
+        b"Attribute VB_GlobalNameSpace = False",
+        b"Attribute VB_Creatable = False",
+        b"Attribute VB_PredeclaredId = True",
+        b"Attribute VB_Exposed = True",
+        b"Attribute VB_TemplateDerived = False",
+        b"Attribute VB_Customizable = True",
+    ];
+    const VB_NAME: &[u8] = b"attribute vb_name = ";
+
+    let mut data = Vec::new();
+    let mut sources = sources.iter();
+    let mut module_name: &[u8] = &[];
+    for (id, record) in records {
+        match id {
+            // PROJECTSYSKIND, PROJECTMODULES, PROJECTCOOKIE, MODULEOFFSET, and
+            // MODULECOOKIE: Id and Size only
+            0x0001 | 0x000f | 0x0013 | 0x0031 | 0x002c => {
+                data.extend_from_slice(&id.to_le_bytes());
+                data.extend_from_slice(&(record.len() as u32).to_le_bytes());
+            }
+            // PROJECTCOMPATVERSION and the *dir* stream's Terminator are excluded.
+            0x004a | 0x0010 => {}
+            // PROJECTVERSION: The Reserved field is fixed at 4.
+            0x0009 => {
+                data.extend_from_slice(&id.to_le_bytes());
+                data.extend_from_slice(&4_u32.to_le_bytes());
+                data.extend_from_slice(record);
+            }
+            // The MODULE Record's Terminator is replaced by the module's source code.
+            0x002b => {
+                if let Some(source) = sources.next() {
+                    // Lines are appended with an LF terminator, skipping the
+                    // `Attribute VB_Name` line and default `Attribute` lines. The module
+                    // name is appended, unless all lines were skipped.
+                    let mut hash_module_name = false;
+                    for line in source_lines(source) {
+                        let is_vb_name = line.len() >= VB_NAME.len()
+                            && line[..VB_NAME.len()].eq_ignore_ascii_case(VB_NAME);
+                        let skip = is_attribute(line)
+                            && (is_vb_name
+                                || DEFAULT_ATTRIBUTES
+                                    .iter()
+                                    .any(|attribute| line.eq_ignore_ascii_case(attribute)));
+                        if !skip {
+                            hash_module_name = true;
+                            data.extend_from_slice(line);
+                            data.push(b'\n');
+                        }
+                    }
+                    if hash_module_name {
+                        data.extend_from_slice(module_name);
+                        data.push(b'\n');
+                    }
+                }
+            }
+            _ => {
+                if *id == 0x0019 {
+                    module_name = record;
+                }
+                data.extend_from_slice(&id.to_le_bytes());
+                data.extend_from_slice(&(record.len() as u32).to_le_bytes());
+                data.extend_from_slice(record);
+            }
+        }
+    }
+
+    data
+}
+
+/// Produces the ProjectNormalizedData of a VBA project, as specified in MS-OVBA
+/// section 2.4.2.6.
+///
+/// `project_stream` holds the raw *PROJECT* stream. Each property contributes its name
+/// and (raw) value, except for `ID`, `Document`, and the encrypted `CMG`, `DPB`, and
+/// `GC` properties. The normalized data of a designer module's storage, as returned by
+/// `designer`, precedes its `BaseClass` property. The `[Host Extender Info]` section
+/// contributes its header (without brackets), followed by each line. The
+/// `[Workspace]` section is excluded.
+pub(crate) fn write_project_normalized_data<F>(
+    project_stream: &[u8],
+    mut designer: F,
+) -> Result<Vec<u8>>
+where
+    F: FnMut(&[u8]) -> Result<Vec<u8>>,
+{
+    const EXCLUDED: &[&[u8]] = &[b"ID", b"Document", b"CMG", b"DPB", b"GC"];
+    const HOST_EXTENDERS: &[u8] = b"[Host Extender Info]";
+
+    let mut data = Vec::new();
+    let mut in_host_extenders = false;
+    for line in source_lines(project_stream) {
+        if line.starts_with(b"[") {
+            if line != HOST_EXTENDERS {
+                break;
+            }
+            in_host_extenders = true;
+            data.extend_from_slice(&HOST_EXTENDERS[1..HOST_EXTENDERS.len() - 1]);
+            continue;
+        }
+        if in_host_extenders {
+            data.extend_from_slice(line);
+            continue;
+        }
+
+        let pos = match line.iter().position(|&b| b == b'=') {
+            Some(pos) => pos,
+            None => continue,
+        };
+        let (name, value) = (&line[..pos], &line[pos + 1..]);
+        if EXCLUDED.contains(&name) {
+            continue;
+        }
+        if name == b"BaseClass" {
+            data.extend(designer(value)?);
+        }
+        data.extend_from_slice(name);
+        data.extend_from_slice(value);
+    }
+
+    Ok(data)
+}
@@ -0,0 +1,103 @@
+//! Golden file tests.
+//!
+//! Every `*.bin` file in *tests/samples* is opened as a VBA project, and a textual dump
+//! of the parsed project is compared against the accompanying `*.txt` file. Set the
+//! environment variable `OVBA_BLESS` to write the dumps instead, e.g. after adding a
+//! sample. Review the result before committing it.
+
+use ovba::{open_project, Project, Reference};
+use std::{
+    env,
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Returns a human-readable description of the parts of `project` covered by the tests.
+fn dump(project: &Project) -> ovba::Result<String> {
+    let information = &project.information;
+    let mut result = String::new();
+    writeln!(result, "Name: {}", information.name()).unwrap();
+    writeln!(result, "SysKind: {}", information.sys_kind).unwrap();
+    writeln!(result, "CodePage: {}", information.code_page).unwrap();
+    writeln!(result, "Version: {:?}", information.version()).unwrap();
+    writeln!(result, "Constants: {}", information.constants()).unwrap();
+
+    for reference in &project.references {
+        let name = reference.name().unwrap_or_default();
+        match reference {
+            Reference::Control(value) => writeln!(
+                result,
+                "Reference: {} (Control) {} {}",
+                name,
+                value.libid_twiddled(),
+                value.guid_string()
+            ),
+            Reference::Original(value) => writeln!(
+                result,
+                "Reference: {} (Original) {}",
+                name,
+                value.libid_original()
+            ),
+            Reference::Registered(value) => {
+                writeln!(result, "Reference: {} (Registered) {}", name, value.libid())
+            }
+            Reference::Project(value) => writeln!(
+                result,
+                "Reference: {} (Project) {}",
+                name,
+                value.libid_absolute()
+            ),
+        }
+        .unwrap();
+    }
+
+    for module in &project.modules {
+        let kind = project.module_kind(&module.name)?;
+        writeln!(result, "Module: {} ({})", module.name, kind).unwrap();
+        for line in project.module_source(&module.name)?.lines() {
+            writeln!(result, "    {}", line).unwrap();
+        }
+    }
+
+    Ok(result)
+}
+
+fn samples() -> Vec<PathBuf> {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/samples");
+    let mut samples = fs::read_dir(directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension() == Some("bin".as_ref()))
+        .collect::<Vec<_>>();
+    samples.sort();
+    samples
+}
+
+#[test]
+fn golden_files() {
+    let bless = env::var_os("OVBA_BLESS").is_some();
+    let samples = samples();
+    assert!(!samples.is_empty(), "no samples found");
+
+    for sample in samples {
+        let project = open_project(fs::read(&sample).unwrap())
+            .unwrap_or_else(|e| panic!("{}: {}", sample.display(), e));
+        let actual = dump(&project).unwrap_or_else(|e| panic!("{}: {}", sample.display(), e));
+
+        let golden = sample.with_extension("txt");
+        if bless {
+            fs::write(&golden, actual).unwrap();
+        } else {
+            let expected = fs::read_to_string(&golden)
+                .unwrap_or_else(|e| panic!("{}: {}", golden.display(), e));
+            // Line endings may have been converted on checkout.
+            assert_eq!(
+                actual,
+                expected.replace("\r\n", "\n"),
+                "{}",
+                sample.display()
+            );
+        }
+    }
+}